@@ -1,6 +1,6 @@
 use std::any::TypeId;
 
-use crate::storage::Component;
+use crate::storage::{Component, ComponentId};
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub struct Entity(pub u32);
@@ -47,6 +47,21 @@ impl Archetype {
         self.entitys.remove(index);
     }
 
+    pub(crate) fn entity_ids(&self) -> &[u32] {
+        &self.entitys
+    }
+
+    // Replaces the order of the entitys, the ids have to be the same ones in any order
+    pub(crate) fn reorder_entities(&mut self, entity_ids: Vec<u32>) {
+        debug_assert_eq!(self.entitys.len(), entity_ids.len());
+        self.entitys = entity_ids;
+    }
+
+    /// Returns every entity of the archetype
+    pub fn entities(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.entitys.iter().map(|id| Entity(*id))
+    }
+
     pub fn contains_entity(&self, entity: &Entity) -> bool {
         self.entitys.contains(&entity.0)
     }
@@ -56,9 +71,10 @@ impl Archetype {
     }
 }
 
-#[derive(Debug, Clone)]
+// Ordered set of the components of an entity, a component is never contained twice
+#[derive(Debug, Clone, Default)]
 pub struct EntityLayout {
-    layout: Vec<TypeId>,
+    layout: Vec<ComponentId>,
 }
 
 impl EntityLayout {
@@ -70,18 +86,37 @@ impl EntityLayout {
     where
         T: Component,
     {
-        let type_id = TypeId::of::<T>();
-        self.layout.push(type_id)
+        self.register_component_id(ComponentId::of::<T>())
+    }
+
+    /// Adds a component to the end of the layout, registering an existing component does nothing
+    pub fn register_component_id(&mut self, component_id: ComponentId) {
+        if !self.contains(component_id) {
+            self.layout.push(component_id)
+        }
+    }
+
+    pub fn unregister_component_id(&mut self, component_id: ComponentId) {
+        self.layout.retain(|id| *id != component_id)
     }
 
     pub fn containes_type(&self, type_id: TypeId) -> bool {
-        self.layout.contains(&type_id)
+        self.contains(ComponentId::from(type_id))
+    }
+
+    pub fn contains(&self, component_id: ComponentId) -> bool {
+        self.layout.contains(&component_id)
+    }
+
+    /// Returns the position of a component in the layout which is also its position in the entitys locations
+    pub fn index_of(&self, component_id: ComponentId) -> Option<usize> {
+        self.layout.iter().position(|id| *id == component_id)
     }
 }
 
 impl IntoIterator for EntityLayout {
-    type Item = TypeId;
-    type IntoIter = <Vec<TypeId> as IntoIterator>::IntoIter;
+    type Item = ComponentId;
+    type IntoIter = <Vec<ComponentId> as IntoIterator>::IntoIter;
 
     fn into_iter(self) -> Self::IntoIter {
         self.layout.into_iter()
@@ -94,7 +129,7 @@ impl PartialEq for EntityLayout {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct ArchetypeStorage {
     // Provides unique indecies for every archetype
     ids: ArchetypeIndex,
@@ -114,6 +149,16 @@ impl ArchetypeStorage {
         self.archetypes.len()
     }
 
+    /// Returns true if no archetype exists
+    pub fn is_empty(&self) -> bool {
+        self.archetypes.is_empty()
+    }
+
+    /// Returns every archetype in the order of their indices
+    pub fn iter(&self) -> impl Iterator<Item = &Archetype> {
+        self.archetypes.iter()
+    }
+
     /// Adds new archetype from its layout and returns a mutable reference to it
     pub fn create_from_layout(&mut self, layout: EntityLayout) -> &mut Archetype {
         let index = self.ids;
@@ -125,6 +170,22 @@ impl ArchetypeStorage {
         self.get_mut(index)
     }
 
+    /// Finds the archetype with the provided layout or creates it if it does not exist yet
+    pub fn find_or_create_from_layout(&mut self, layout: EntityLayout) -> &mut Archetype {
+        match self.find_from_layout(&layout).map(Archetype::index) {
+            Some(index) => self.get_mut(index),
+            None => self.create_from_layout(layout),
+        }
+    }
+
+    /// Returns every archetype whose layout contains a component
+    pub fn find_with_component(&self, component_id: ComponentId) -> Vec<&Archetype> {
+        self.archetypes
+            .iter()
+            .filter(|archetype| archetype.layout.contains(component_id))
+            .collect()
+    }
+
     /// Get archetype from index as a reference
     pub fn get(&self, index: ArchetypeIndex) -> &Archetype {
         self.archetypes.get(index as usize).unwrap()
@@ -171,13 +232,26 @@ impl ArchetypeStorage {
             // There should be only 1 archetype that matches the layout
             assert_eq!(archetypes.len(), 1);
 
-            let index = archetypes.get(0).unwrap().index;
+            let index = archetypes.first().unwrap().index;
             let archetype = self.archetypes.get_mut(index as usize).unwrap();
 
             Some(archetype)
         }
     }
 
+    /// Returns every entity of archetypes whose layout contains all the provided components
+    pub fn entities_with(&self, components: &[ComponentId]) -> Vec<Entity> {
+        self.archetypes
+            .iter()
+            .filter(|archetype| {
+                components
+                    .iter()
+                    .all(|component_id| archetype.layout.contains(*component_id))
+            })
+            .flat_map(Archetype::entities)
+            .collect()
+    }
+
     /// Find a an entitys archetype and returns a reference to it
     pub fn find_from_entity(&self, entity: &Entity) -> Option<&Archetype> {
         let archetypes: Vec<&Archetype> = self
@@ -211,7 +285,7 @@ impl ArchetypeStorage {
             // An Entity can't be assigned to two archetypes
             assert_eq!(archetypes.len(), 1);
 
-            let index = archetypes.get(0).unwrap().index;
+            let index = archetypes.first().unwrap().index;
             let archetype = self.archetypes.get_mut(index as usize).unwrap();
             Some(archetype)
         }
@@ -222,7 +296,10 @@ impl ArchetypeStorage {
 mod tests {
     use std::borrow::BorrowMut;
 
-    use crate::archetype::{Archetype, Entity};
+    use crate::{
+        archetype::{Archetype, Entity},
+        storage::ComponentId,
+    };
 
     use super::{ArchetypeStorage, EntityLayout};
 
@@ -277,4 +354,18 @@ mod tests {
             .borrow_mut()
         )
     }
+
+    #[test]
+    fn layout_never_contains_type_twice() {
+        let mut layout = EntityLayout::new();
+        layout.register_component_id(ComponentId::Dynamic(0));
+        layout.register_component_id(ComponentId::Dynamic(1));
+        layout.register_component_id(ComponentId::Dynamic(0));
+
+        let mut expected = EntityLayout::new();
+        expected.register_component_id(ComponentId::Dynamic(0));
+        expected.register_component_id(ComponentId::Dynamic(1));
+
+        assert_eq!(layout, expected);
+    }
 }
@@ -0,0 +1,292 @@
+use std::{
+    any::type_name,
+    collections::HashMap,
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+    ptr::NonNull,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{
+    error::EcsError,
+    storage::{Component, ComponentId, ComponentStorages, ErasedStorage},
+};
+
+// Borrow flag value while a storage is borrowed mutable, any other value counts the shared borrows
+const MUTABLE: usize = usize::MAX;
+
+// Storage together with the flag that tracks how it is borrowed
+struct BorrowCell {
+    storage: NonNull<dyn ErasedStorage>,
+    flag: AtomicUsize,
+}
+
+impl BorrowCell {
+    fn acquire(&self) -> bool {
+        self.flag
+            .fetch_update(Ordering::Acquire, Ordering::Relaxed, |flag| {
+                (flag != MUTABLE).then(|| flag + 1)
+            })
+            .is_ok()
+    }
+
+    fn acquire_mut(&self) -> bool {
+        self.flag
+            .compare_exchange(0, MUTABLE, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
+    }
+}
+
+/// Hands out borrows of different storages at the same time, like a `RefCell` per storage
+/// Conflicting borrows of the same storage fail with an error naming the component
+/// The borrows can be shared between threads, so every thread can work on its own storages
+pub struct StorageBorrows<'a> {
+    cells: HashMap<ComponentId, BorrowCell>,
+    storages: PhantomData<&'a mut ComponentStorages>,
+}
+
+// Safety: The borrows hold the storages exclusively and every storage is `Send + Sync`
+// Threads that share the borrows only reach a storage after its atomic flag allowed the access,
+// the acquire and release orderings of the flags make the changes of one borrow visible to the next
+unsafe impl Send for StorageBorrows<'_> {}
+unsafe impl Sync for StorageBorrows<'_> {}
+
+impl<'a> StorageBorrows<'a> {
+    pub(crate) fn new(
+        storages: impl Iterator<Item = (&'a ComponentId, &'a mut Box<dyn ErasedStorage>)>,
+    ) -> Self {
+        let cells = storages
+            .map(|(component_id, storage)| {
+                let cell = BorrowCell {
+                    storage: NonNull::from(storage.as_mut()),
+                    flag: AtomicUsize::new(0),
+                };
+                (*component_id, cell)
+            })
+            .collect();
+
+        Self {
+            cells,
+            storages: PhantomData,
+        }
+    }
+
+    /// Borrows the storage of component `C`
+    /// Panics if the storage is borrowed mutable or does not exist
+    pub fn borrow<C: Component>(&self) -> StorageRef<'_, C::Storage> {
+        self.try_borrow::<C>()
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Borrows the storage of component `C` mutable
+    /// Panics if the storage is borrowed already or does not exist
+    pub fn borrow_mut<C: Component>(&self) -> StorageRefMut<'_, C::Storage> {
+        self.try_borrow_mut::<C>()
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Borrows the storage of component `C`
+    pub fn try_borrow<C: Component>(&self) -> Result<StorageRef<'_, C::Storage>, EcsError> {
+        let cell = self.cell::<C>()?;
+        if !cell.acquire() {
+            return Err(EcsError::StorageBorrowed {
+                component: type_name::<C>(),
+                mutably: true,
+            });
+        }
+
+        // Safety: The flag guarantees that the storage is not borrowed mutable
+        let storage = unsafe { cell.storage.as_ref() }.as_any().downcast_ref();
+        match storage {
+            Some(storage) => Ok(StorageRef {
+                storage,
+                flag: &cell.flag,
+            }),
+            None => {
+                cell.flag.fetch_sub(1, Ordering::Release);
+                Err(EcsError::StorageTypeMismatch {
+                    component: type_name::<C>(),
+                })
+            }
+        }
+    }
+
+    /// Borrows the storage of component `C` mutable
+    pub fn try_borrow_mut<C: Component>(&self) -> Result<StorageRefMut<'_, C::Storage>, EcsError> {
+        let cell = self.cell::<C>()?;
+        if !cell.acquire_mut() {
+            return Err(EcsError::StorageBorrowed {
+                component: type_name::<C>(),
+                mutably: cell.flag.load(Ordering::Relaxed) == MUTABLE,
+            });
+        }
+
+        // Safety: The flag guarantees that the storage is not borrowed at all
+        let mut storage = cell.storage;
+        let storage = unsafe { storage.as_mut() }.as_any_mut().downcast_mut();
+        match storage {
+            Some(storage) => Ok(StorageRefMut {
+                storage,
+                flag: &cell.flag,
+            }),
+            None => {
+                cell.flag.store(0, Ordering::Release);
+                Err(EcsError::StorageTypeMismatch {
+                    component: type_name::<C>(),
+                })
+            }
+        }
+    }
+
+    fn cell<C: Component>(&self) -> Result<&BorrowCell, EcsError> {
+        let component_id = ComponentId::of::<C>();
+
+        self.cells
+            .get(&component_id)
+            .ok_or(EcsError::MissingStorage(component_id))
+    }
+}
+
+/// Shared borrow of a storage, releases the borrow when dropped
+pub struct StorageRef<'b, S> {
+    storage: &'b S,
+    flag: &'b AtomicUsize,
+}
+
+impl<S> Deref for StorageRef<'_, S> {
+    type Target = S;
+
+    fn deref(&self) -> &S {
+        self.storage
+    }
+}
+
+impl<S> Drop for StorageRef<'_, S> {
+    fn drop(&mut self) {
+        self.flag.fetch_sub(1, Ordering::Release);
+    }
+}
+
+/// Mutable borrow of a storage, releases the borrow when dropped
+pub struct StorageRefMut<'b, S> {
+    storage: &'b mut S,
+    flag: &'b AtomicUsize,
+}
+
+impl<S> Deref for StorageRefMut<'_, S> {
+    type Target = S;
+
+    fn deref(&self) -> &S {
+        self.storage
+    }
+}
+
+impl<S> DerefMut for StorageRefMut<'_, S> {
+    fn deref_mut(&mut self) -> &mut S {
+        self.storage
+    }
+}
+
+impl<S> Drop for StorageRefMut<'_, S> {
+    fn drop(&mut self) {
+        self.flag.store(0, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{any::type_name, thread};
+
+    use crate::{
+        error::EcsError,
+        storage::{Component, Storage, VecStorage},
+        world::World,
+    };
+
+    #[derive(Debug, PartialEq)]
+    struct Position(f32);
+
+    impl Component for Position {
+        type Storage = VecStorage<Self>;
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Velocity(f32);
+
+    impl Component for Velocity {
+        type Storage = VecStorage<Self>;
+    }
+
+    #[test]
+    fn borrow_disjoint_storages() {
+        let mut world = World::new();
+        let ball = world.spawn(Position(0.0));
+        world.entry_mut(&ball).add_component(Velocity(2.0));
+
+        let storages = world.components.borrows();
+        let mut positions = storages.borrow_mut::<Position>();
+        let velocities = storages.borrow::<Velocity>();
+        let other_velocities = storages.borrow::<Velocity>();
+
+        positions.get_component_mut(0).unwrap().0 += velocities.as_slice()[0].0;
+
+        assert_eq!(
+            storages.try_borrow::<Position>().err(),
+            Some(EcsError::StorageBorrowed {
+                component: type_name::<Position>(),
+                mutably: true,
+            })
+        );
+        assert_eq!(
+            storages.try_borrow_mut::<Velocity>().err(),
+            Some(EcsError::StorageBorrowed {
+                component: type_name::<Velocity>(),
+                mutably: false,
+            })
+        );
+
+        drop(positions);
+        drop((velocities, other_velocities));
+        assert!(storages.try_borrow_mut::<Velocity>().is_ok());
+        assert_eq!(storages.borrow::<Position>().as_slice(), &[Position(2.0)]);
+    }
+
+    #[test]
+    fn borrow_storages_from_several_threads() {
+        let mut world = World::new();
+        let ball = world.spawn(Position(0.0));
+        world.entry_mut(&ball).add_component(Velocity(2.0));
+
+        let storages = world.components.borrows();
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                storages
+                    .borrow_mut::<Position>()
+                    .get_component_mut(0)
+                    .unwrap()
+                    .0 = 1.0
+            });
+            scope.spawn(|| {
+                storages
+                    .borrow_mut::<Velocity>()
+                    .get_component_mut(0)
+                    .unwrap()
+                    .0 = 3.0
+            });
+        });
+
+        assert_eq!(storages.borrow::<Position>().as_slice(), &[Position(1.0)]);
+        assert_eq!(storages.borrow::<Velocity>().as_slice(), &[Velocity(3.0)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "is already borrowed mutably")]
+    fn conflicting_borrow_panics() {
+        let mut world = World::new();
+        world.spawn(Position(0.0));
+
+        let storages = world.components.borrows();
+        let _positions = storages.borrow_mut::<Position>();
+        storages.borrow_mut::<Position>();
+    }
+}
@@ -0,0 +1,260 @@
+use std::{marker::PhantomData, ptr::NonNull, slice, vec};
+
+use crate::{
+    archetype::{ArchetypeIndex, Entity},
+    storage::{Component, ComponentId},
+    world::World,
+};
+
+/// Components that are queried as slices, `&C` for shared and `&mut C` for mutable access
+/// Tuples of up to four of them query several components at once
+pub trait ChunkQuery {
+    type Chunk<'w>;
+
+    // Queried components and if they are accessed mutable, in the order of the chunk
+    fn components() -> Vec<(ComponentId, bool)>;
+
+    /// Builds the slices of a chunk
+    ///
+    /// # Safety
+    /// Every pointer has to point to `len` components of its queried type that lie next to each other
+    /// Components accessed mutable must not be accessed in any other way for `'w`
+    unsafe fn from_ptrs<'w>(ptrs: &[NonNull<u8>], len: usize) -> Self::Chunk<'w>;
+}
+
+impl<C: Component> ChunkQuery for &C {
+    type Chunk<'w> = &'w [C];
+
+    fn components() -> Vec<(ComponentId, bool)> {
+        vec![(ComponentId::of::<C>(), false)]
+    }
+
+    unsafe fn from_ptrs<'w>(ptrs: &[NonNull<u8>], len: usize) -> Self::Chunk<'w> {
+        slice::from_raw_parts(ptrs[0].cast::<C>().as_ptr(), len)
+    }
+}
+
+impl<C: Component> ChunkQuery for &mut C {
+    type Chunk<'w> = &'w mut [C];
+
+    fn components() -> Vec<(ComponentId, bool)> {
+        vec![(ComponentId::of::<C>(), true)]
+    }
+
+    unsafe fn from_ptrs<'w>(ptrs: &[NonNull<u8>], len: usize) -> Self::Chunk<'w> {
+        slice::from_raw_parts_mut(ptrs[0].cast::<C>().as_ptr(), len)
+    }
+}
+
+macro_rules! impl_chunk_query {
+    ($($name:ident),*) => {
+        impl<$($name: ChunkQuery),*> ChunkQuery for ($($name,)*) {
+            type Chunk<'w> = ($($name::Chunk<'w>,)*);
+
+            fn components() -> Vec<(ComponentId, bool)> {
+                let mut components = Vec::new();
+                $(components.extend($name::components());)*
+                components
+            }
+
+            #[allow(unused_assignments)]
+            unsafe fn from_ptrs<'w>(ptrs: &[NonNull<u8>], len: usize) -> Self::Chunk<'w> {
+                let mut offset = 0;
+
+                ($({
+                    let count = $name::components().len();
+                    let chunk = $name::from_ptrs(&ptrs[offset..offset + count], len);
+                    offset += count;
+                    chunk
+                },)*)
+            }
+        }
+    };
+}
+
+impl_chunk_query!(A);
+impl_chunk_query!(A, B);
+impl_chunk_query!(A, B, C);
+impl_chunk_query!(A, B, C, D);
+
+/// Iterator over the chunks of a chunk query
+pub struct QueryChunks<'w, Q: ChunkQuery> {
+    chunks: vec::IntoIter<(Vec<NonNull<u8>>, usize)>,
+    world: PhantomData<&'w mut World>,
+    query: PhantomData<fn() -> Q>,
+}
+
+impl<'w, Q: ChunkQuery> Iterator for QueryChunks<'w, Q> {
+    type Item = Q::Chunk<'w>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (ptrs, len) = self.chunks.next()?;
+
+        // Safety: Chunks never overlap and the world stays borrowed mutable for 'w
+        Some(unsafe { Q::from_ptrs(&ptrs, len) })
+    }
+}
+
+impl World {
+    /// Iterates over the components of every archetype that has all queried components
+    /// Each archetype is split into chunks of at most `chunk_size` entities, the slices of a chunk
+    /// line up so index i of every slice belongs to the same entity
+    /// Storages are compacted first if the components of an archetype are not next to each other
+    /// Components accessed mutable are marked as changed
+    /// Panics if the chunk size is zero or a component is queried twice
+    pub fn query_chunks<Q: ChunkQuery>(&mut self, chunk_size: usize) -> QueryChunks<'_, Q> {
+        assert!(chunk_size > 0, "Chunk size can't be zero!");
+
+        let components = Q::components();
+        for (index, (component_id, _)) in components.iter().enumerate() {
+            assert!(
+                components[..index]
+                    .iter()
+                    .all(|(other, _)| other != component_id),
+                "Component {} is queried twice!",
+                self.component_name(*component_id)
+            );
+
+            if !self.is_contiguous(*component_id) {
+                self.compact_component(*component_id);
+            }
+        }
+
+        let change_tick = self.change_tick();
+        let mut chunks = Vec::new();
+        for index in 0..self.archetypes.len() as ArchetypeIndex {
+            let archetype = self.archetypes.get(index);
+            let entity_ids = archetype.entity_ids();
+            let matches = components
+                .iter()
+                .all(|(component_id, _)| archetype.layout().contains(*component_id));
+            if !matches || entity_ids.is_empty() {
+                continue;
+            }
+
+            // Pointer to the first component of the archetype and the component size per queried component
+            let mut starts = Vec::new();
+            for (component_id, mutable) in &components {
+                let position = archetype.layout().index_of(*component_id).unwrap();
+
+                if *mutable {
+                    for id in entity_ids {
+                        self.locations.get_mut(&Entity(*id)).unwrap()[position]
+                            .set_changed(change_tick);
+                        self.indexes.mark_dirty(*component_id, Entity(*id));
+                    }
+                }
+
+                let first =
+                    self.locations.get(&Entity(entity_ids[0])).unwrap()[position].component();
+                let storage = self.components.storage_raw_mut(*component_id).unwrap();
+                let size = storage.item_size();
+                assert!(
+                    first + entity_ids.len() <= storage.len(),
+                    "Components of an archetype are out of bounds!"
+                );
+
+                // Safety: `ErasedStorage` guarantees the stride and the components of the archetype are in bounds
+                let start = unsafe { storage.as_mut_ptr().add(first * size) };
+                starts.push((start, size));
+            }
+
+            for offset in (0..entity_ids.len()).step_by(chunk_size) {
+                let ptrs = starts
+                    .iter()
+                    .map(|(start, size)| unsafe { start.add(offset * size) })
+                    .collect();
+
+                chunks.push((ptrs, chunk_size.min(entity_ids.len() - offset)));
+            }
+        }
+
+        QueryChunks {
+            chunks: chunks.into_iter(),
+            world: PhantomData,
+            query: PhantomData,
+        }
+    }
+
+    // Checks that the components of every archetype lie next to each other in entity order
+    fn is_contiguous(&self, component_id: ComponentId) -> bool {
+        self.archetypes
+            .find_with_component(component_id)
+            .iter()
+            .all(|archetype| {
+                let position = archetype.layout().index_of(component_id).unwrap();
+                let mut indices = archetype
+                    .entity_ids()
+                    .iter()
+                    .map(|id| self.locations.get(&Entity(*id)).unwrap()[position].component());
+
+                match indices.next() {
+                    Some(first) => indices
+                        .enumerate()
+                        .all(|(offset, index)| index == first + offset + 1),
+                    None => true,
+                }
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        storage::{Component, VecStorage},
+        world::World,
+    };
+
+    #[derive(Debug, PartialEq)]
+    struct Position(f32);
+
+    impl Component for Position {
+        type Storage = VecStorage<Self>;
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Velocity(f32);
+
+    impl Component for Velocity {
+        type Storage = VecStorage<Self>;
+    }
+
+    #[test]
+    fn chunks_line_up_per_archetype() {
+        let mut world = World::new();
+
+        let mut particles = Vec::new();
+        for i in 0..10 {
+            let particle = world.spawn(Position(i as f32));
+            world.spawn(Position(-1.0));
+            world
+                .entry_mut(&particle)
+                .add_component(Velocity(i as f32 * 10.0));
+            particles.push(particle);
+        }
+        world.despawn(&particles.remove(3));
+
+        let mut lens = Vec::new();
+        for (positions, velocities) in world.query_chunks::<(&mut Position, &Velocity)>(4) {
+            for (position, velocity) in positions.iter_mut().zip(velocities) {
+                position.0 += velocity.0;
+            }
+            lens.push(positions.len());
+        }
+
+        assert_eq!(lens, vec![4, 4, 1]);
+        for (i, particle) in particles.iter().enumerate() {
+            let i = if i < 3 { i } else { i + 1 } as f32;
+            assert_eq!(
+                *world.entry(particle).get_component::<Position>().unwrap(),
+                Position(i * 11.0)
+            );
+        }
+
+        let positions: usize = world
+            .query_chunks::<(&Position,)>(64)
+            .map(|(positions,)| positions.len())
+            .sum();
+        assert_eq!(positions, 19);
+    }
+}
@@ -0,0 +1,180 @@
+use std::fmt;
+
+use crate::{archetype::Entity, storage::Component, world::World};
+
+/// Change to a world that is recorded now and applied later
+pub type Command = Box<dyn FnOnce(&mut World) + Send>;
+
+/// Records changes to a world while only having shared access to it
+/// Entities spawned through the buffer are reserved right away so their handles can be used before the buffer is applied
+#[derive(Default)]
+pub struct CommandBuffer {
+    commands: Vec<Command>,
+}
+
+impl CommandBuffer {
+    pub fn new() -> Self {
+        Self {
+            commands: Vec::new(),
+        }
+    }
+
+    /// Reserves a new entity that gets component `C` once the buffer is applied
+    pub fn spawn<C: Component>(&mut self, world: &World, component: C) -> Entity {
+        let entity = world.reserve_entity();
+        self.add_component(entity, component);
+
+        entity
+    }
+
+    /// Adds component `C` to an entity, it replaces the component if the entity already has one
+    /// Nothing happens if the entity is despawned by the time the buffer is applied
+    pub fn add_component<C: Component>(&mut self, entity: Entity, component: C) {
+        self.push(move |world| {
+            if let Ok(mut entry) = world.try_entry_mut(&entity) {
+                entry.add_component(component);
+            }
+        });
+    }
+
+    /// Removes component `C` from an entity
+    /// Nothing happens if the entity is despawned by the time the buffer is applied
+    pub fn remove_component<C: Component>(&mut self, entity: Entity) {
+        self.push(move |world| {
+            if let Ok(mut entry) = world.try_entry_mut(&entity) {
+                entry.remove_component::<C>();
+            }
+        });
+    }
+
+    /// Removes an entity, nothing happens if it is despawned already
+    pub fn despawn(&mut self, entity: Entity) {
+        self.push(move |world| {
+            world.despawn(&entity);
+        });
+    }
+
+    /// Records any change to the world
+    pub fn push(&mut self, command: impl FnOnce(&mut World) + Send + 'static) {
+        self.commands.push(Box::new(command));
+    }
+
+    /// Applies every recorded change in the order they were recorded
+    pub fn apply(self, world: &mut World) {
+        world.flush();
+
+        for command in self.commands {
+            command(world);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+}
+
+impl fmt::Debug for CommandBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CommandBuffer")
+            .field("commands", &self.commands.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        error::EcsError,
+        storage::{Component, VecStorage},
+        world::World,
+    };
+
+    use super::CommandBuffer;
+
+    #[derive(Debug, PartialEq)]
+    struct Velocity(f32);
+
+    impl Component for Velocity {
+        type Storage = VecStorage<Self>;
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Bullet;
+
+    impl Component for Bullet {
+        type Storage = VecStorage<Self>;
+    }
+
+    #[test]
+    fn reserve_entities_through_shared_reference() {
+        let mut world = World::new();
+        let player = world.spawn(Velocity(1.0));
+
+        let reserved = world.reserve_entity();
+        let other = world.reserve_entity();
+        assert_ne!(reserved, other);
+        assert_eq!(
+            world.try_entry(&reserved).err(),
+            Some(EcsError::NoSuchEntity(reserved))
+        );
+
+        let spawned = world.spawn(Velocity(2.0));
+        assert_eq!(spawned.id(), other.id() + 1);
+        assert!(world
+            .entry(&reserved)
+            .archetype()
+            .layout()
+            .clone()
+            .into_iter()
+            .next()
+            .is_none());
+        assert_eq!(
+            *world.entry(&player).get_component::<Velocity>().unwrap(),
+            Velocity(1.0)
+        );
+    }
+
+    #[test]
+    fn command_buffers_apply_in_order() {
+        let mut world = World::new();
+        let gun = world.spawn(Velocity(0.0));
+
+        let mut first = CommandBuffer::new();
+        let mut second = CommandBuffer::new();
+        let bullet = first.spawn(&world, Velocity(10.0));
+        first.add_component(bullet, Bullet);
+        let dud = second.spawn(&world, Bullet);
+        second.despawn(gun);
+        second.remove_component::<Bullet>(bullet);
+
+        assert_eq!(first.len(), 2);
+        first.apply(&mut world);
+        second.apply(&mut world);
+
+        assert!(world.try_entry(&gun).is_err());
+        assert!(world.entry(&dud).get_component::<Bullet>().is_some());
+        assert!(world.entry(&bullet).get_component::<Bullet>().is_none());
+        assert_eq!(
+            *world.entry(&bullet).get_component::<Velocity>().unwrap(),
+            Velocity(10.0)
+        );
+    }
+
+    #[test]
+    fn commands_skip_despawned_entities() {
+        let mut world = World::new();
+        let target = world.spawn(Velocity(1.0));
+
+        let mut commands = CommandBuffer::new();
+        commands.despawn(target);
+        commands.add_component(target, Bullet);
+        commands.remove_component::<Velocity>(target);
+        commands.apply(&mut world);
+
+        assert!(world.try_entry(&target).is_err());
+    }
+}
@@ -0,0 +1,130 @@
+use crate::{archetype::Entity, storage::ComponentId, world::World};
+
+impl World {
+    /// Removes every component that no entity owns anymore from all storages
+    /// Components are reordered so the components of an archetype lie next to each other
+    /// Returns how many bytes the removed components took up
+    pub fn compact(&mut self) -> usize {
+        let component_ids: Vec<ComponentId> = self.components.component_ids().collect();
+
+        component_ids
+            .into_iter()
+            .map(|component_id| self.compact_component(component_id))
+            .sum()
+    }
+
+    /// Removes every component of one type that no entity owns anymore and rewrites the locations
+    /// Returns how many bytes the removed components took up
+    pub fn compact_component(&mut self, component_id: ComponentId) -> usize {
+        let Some(storage) = self.components.get_storage_raw(component_id) else {
+            return 0;
+        };
+        let len = storage.len();
+
+        // Index every owned component has now, in the order it will have afterwards
+        let mut owners = Vec::new();
+        let mut sources = Vec::new();
+        for archetype in self.archetypes.find_with_component(component_id) {
+            let position = archetype.layout().index_of(component_id).unwrap();
+
+            for id in archetype.entity_ids() {
+                let entity = Entity(*id);
+                let locations = self
+                    .locations
+                    .get(&entity)
+                    .expect("Entity has no locations!");
+
+                sources.push(locations[position].component());
+                owners.push((entity, position));
+            }
+        }
+
+        let storage = self.components.storage_raw_mut(component_id).unwrap();
+
+        // Tracks which original component is at which index while swapping them into order
+        let mut originals: Vec<usize> = (0..len).collect();
+        let mut indices: Vec<usize> = (0..len).collect();
+        for (target, source) in sources.iter().enumerate() {
+            let current = indices[*source];
+
+            if current != target {
+                storage.swap(target, current);
+
+                let displaced = originals[target];
+                originals.swap(target, current);
+                indices[displaced] = current;
+                indices[*source] = target;
+            }
+        }
+
+        storage.truncate(sources.len());
+        let reclaimed = (len - sources.len()) * storage.item_size();
+
+        for (index, (entity, position)) in owners.into_iter().enumerate() {
+            self.locations.get_mut(&entity).unwrap()[position].set_component(index);
+        }
+
+        reclaimed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::mem;
+
+    use crate::{
+        storage::{Component, VecStorage},
+        world::World,
+    };
+
+    #[derive(Debug, PartialEq)]
+    struct Health(f32);
+
+    impl Component for Health {
+        type Storage = VecStorage<Self>;
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Armor(u32);
+
+    impl Component for Armor {
+        type Storage = VecStorage<Self>;
+    }
+
+    #[test]
+    fn compact_removes_orphaned_components() {
+        let mut world = World::new();
+
+        let knight = world.spawn(Health(100.0));
+        let ghost = world.spawn(Health(1.0));
+        let rogue = world.spawn(Health(60.0));
+        let archer = world.spawn(Health(40.0));
+        world.entry_mut(&knight).add_component(Armor(10));
+        world.entry_mut(&archer).add_component(Armor(2));
+
+        world.despawn(&ghost);
+        world.entry_mut(&rogue).remove_component::<Health>();
+        world.entry_mut(&rogue).add_component(Health(55.0));
+
+        assert_eq!(world.compact(), 2 * mem::size_of::<Health>());
+        assert_eq!(world.compact(), 0);
+
+        // Components of the same archetype end up next to each other
+        assert_eq!(
+            world.query::<Health>(),
+            &[Health(55.0), Health(100.0), Health(40.0)]
+        );
+        assert_eq!(
+            *world.entry(&archer).get_component::<Health>().unwrap(),
+            Health(40.0)
+        );
+        assert_eq!(
+            *world.entry(&archer).get_component::<Armor>().unwrap(),
+            Armor(2)
+        );
+        assert_eq!(
+            *world.entry(&rogue).get_component::<Health>().unwrap(),
+            Health(55.0)
+        );
+    }
+}
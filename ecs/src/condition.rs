@@ -0,0 +1,170 @@
+use std::{any::Any, fmt};
+
+use crate::world::World;
+
+/// Predicate that decides every time a system would run if it actually runs
+pub struct RunCondition(Box<dyn FnMut(&World) -> bool + Send>);
+
+impl RunCondition {
+    pub fn new(condition: impl FnMut(&World) -> bool + Send + 'static) -> Self {
+        Self(Box::new(condition))
+    }
+
+    pub fn evaluate(&mut self, world: &World) -> bool {
+        (self.0)(world)
+    }
+}
+
+impl fmt::Debug for RunCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RunCondition").finish_non_exhaustive()
+    }
+}
+
+/// Turns predicates into run conditions so they can be combined
+/// Combined conditions short circuit like `&&` and `||`
+pub trait IntoRunCondition {
+    fn into_condition(self) -> RunCondition;
+
+    /// Is true when both conditions are true
+    fn and(self, other: impl IntoRunCondition) -> RunCondition
+    where
+        Self: Sized,
+    {
+        let mut a = self.into_condition();
+        let mut b = other.into_condition();
+        RunCondition::new(move |world| a.evaluate(world) && b.evaluate(world))
+    }
+
+    /// Is true when at least one of the conditions is true
+    fn or(self, other: impl IntoRunCondition) -> RunCondition
+    where
+        Self: Sized,
+    {
+        let mut a = self.into_condition();
+        let mut b = other.into_condition();
+        RunCondition::new(move |world| a.evaluate(world) || b.evaluate(world))
+    }
+}
+
+impl IntoRunCondition for RunCondition {
+    fn into_condition(self) -> RunCondition {
+        self
+    }
+}
+
+impl<F: FnMut(&World) -> bool + Send + 'static> IntoRunCondition for F {
+    fn into_condition(self) -> RunCondition {
+        RunCondition::new(self)
+    }
+}
+
+/// Is true when the condition is false
+pub fn not(condition: impl IntoRunCondition) -> RunCondition {
+    let mut condition = condition.into_condition();
+    RunCondition::new(move |world| !condition.evaluate(world))
+}
+
+/// Is true while resource `R` exists
+pub fn resource_exists<R: Any + Send + Sync>() -> RunCondition {
+    RunCondition::new(|world| world.contains_resource::<R>())
+}
+
+/// Is true while resource `R` exists and matches the predicate
+pub fn resource_matches<R: Any + Send + Sync>(
+    predicate: impl Fn(&R) -> bool + Send + 'static,
+) -> RunCondition {
+    RunCondition::new(move |world| world.resource::<R>().is_some_and(&predicate))
+}
+
+/// Is true on the first and then every `n`th time it is evaluated
+/// It counts evaluations, not change ticks, so a condition of a fixed stage counts every step
+/// Panics if `n` is zero
+pub fn every(n: u32) -> RunCondition {
+    assert!(n > 0, "Condition can't be true every 0 times!");
+
+    let mut count = 0;
+    RunCondition::new(move |_| {
+        let run = count == 0;
+        count = (count + 1) % n;
+        run
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        schedule::{IntoSystemConfig, Schedule, UPDATE},
+        world::World,
+    };
+
+    use super::{every, not, resource_exists, resource_matches, IntoRunCondition};
+
+    #[derive(Debug, PartialEq)]
+    struct Paused(bool);
+
+    struct Explosion;
+
+    #[derive(Debug, Default)]
+    struct Log(Vec<&'static str>);
+
+    fn log(entry: &'static str) -> impl FnMut(&mut World) + Send {
+        move |world| world.resource_mut::<Log>().unwrap().0.push(entry)
+    }
+
+    #[test]
+    fn systems_run_only_when_conditions_hold() {
+        let mut world = World::new();
+        world.insert_resource(Log::default());
+        world.insert_resource(Paused(false));
+
+        let running = || not(resource_matches(|paused: &Paused| paused.0));
+
+        let mut schedule = Schedule::new();
+        schedule
+            .add_stage(UPDATE)
+            .add_system(UPDATE, log("physics").run_if(running()))
+            .add_system(UPDATE, log("pause menu").run_if(not(running())))
+            .add_system(
+                UPDATE,
+                log("shake").run_if(resource_exists::<Explosion>().and(running())),
+            )
+            .add_system(
+                UPDATE,
+                log("autosave").run_if(every(2).or(resource_exists::<Explosion>())),
+            );
+
+        let mut frame = |world: &mut World| {
+            world.resource_mut::<Log>().unwrap().0.clear();
+            schedule.run(world);
+            world.resource::<Log>().unwrap().0.clone()
+        };
+
+        assert_eq!(frame(&mut world), vec!["physics", "autosave"]);
+
+        world.insert_resource(Explosion);
+        assert_eq!(frame(&mut world), vec!["physics", "shake", "autosave"]);
+
+        world.remove_resource::<Explosion>();
+        world.insert_resource(Paused(true));
+        assert_eq!(frame(&mut world), vec!["pause menu", "autosave"]);
+        assert_eq!(frame(&mut world), vec!["pause menu"]);
+    }
+
+    #[test]
+    fn every_counts_evaluations() {
+        let world = World::new();
+        let mut condition = every(3);
+        let runs: Vec<bool> = (0..7).map(|_| condition.evaluate(&world)).collect();
+        assert_eq!(runs, vec![true, false, false, true, false, false, true]);
+
+        let mut always = every(1);
+        assert!((0..3).all(|_| always.evaluate(&world)));
+    }
+
+    #[test]
+    #[should_panic(expected = "every 0 times")]
+    fn every_zero_panics() {
+        every(0);
+    }
+}
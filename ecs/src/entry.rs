@@ -1,18 +1,19 @@
-use std::{
-    any::{Any, TypeId},
-    collections::HashMap,
-};
+use std::{any::type_name, collections::HashMap, mem, ptr::NonNull};
 
 use crate::{
-    archetype::{Archetype, ArchetypeStorage, Entity},
-    location::{EntityLocation, LocationMap},
-    storage::{Component, ComponentStorages, Storage},
+    archetype::{Archetype, ArchetypeIndex, Entity, EntityLayout},
+    error::EcsError,
+    hooks::HookKind,
+    location::{EntityLocation, Tick},
+    required::RequiredComponents,
+    storage::{BlobStorage, Component, ComponentId, ErasedStorage, Storage},
+    world::World,
 };
 
 pub struct EntryRef<'a> {
     entity: &'a Entity,
     archetype: &'a Archetype,
-    components: HashMap<TypeId, &'a Box<dyn Any>>,
+    components: HashMap<ComponentId, &'a dyn ErasedStorage>,
     locations: &'a [EntityLocation],
 }
 
@@ -20,7 +21,7 @@ impl<'a> EntryRef<'a> {
     pub fn new(
         entity: &'a Entity,
         archetype: &'a Archetype,
-        components: HashMap<TypeId, &'a Box<dyn Any>>,
+        components: HashMap<ComponentId, &'a dyn ErasedStorage>,
         locations: &'a [EntityLocation],
     ) -> Self {
         Self {
@@ -40,48 +41,62 @@ impl<'a> EntryRef<'a> {
     }
 
     pub fn get_component<C: Component>(&self) -> Option<&'a C> {
-        let type_id = TypeId::of::<C>();
+        let component_id = ComponentId::of::<C>();
 
-        let index = self
-            .archetype
-            .layout()
-            .clone()
-            .into_iter()
-            .position(|component_id| component_id == type_id)
-            .unwrap();
+        let index = self.archetype.layout().index_of(component_id)?;
 
         let location = &self.locations[index];
 
-        let unknown_storage = *self.components.get(&type_id).unwrap();
+        let unknown_storage = *self.components.get(&component_id)?;
 
-        if let Some(storage) = unknown_storage.downcast_ref::<C::Storage>() {
-            Some(storage.get_component(location.component()).unwrap())
+        if let Some(storage) = unknown_storage.as_any().downcast_ref::<C::Storage>() {
+            storage.get_component(location.component())
         } else {
             None
         }
     }
+
+    /// Like `get_component` but tells which entity and component are missing
+    pub fn try_get_component<C: Component>(&self) -> Result<&'a C, EcsError> {
+        self.get_component::<C>().ok_or(EcsError::MissingComponent {
+            entity: *self.entity,
+            component: type_name::<C>(),
+        })
+    }
+
+    /// Returns the tick at which component `C` was added or last accessed mutably
+    pub fn changed_tick<C: Component>(&self) -> Option<Tick> {
+        let index = self.archetype.layout().index_of(ComponentId::of::<C>())?;
+
+        Some(self.locations[index].changed())
+    }
+
+    /// Checks if component `C` changed after the provided tick
+    pub fn is_changed<C: Component>(&self, since: Tick) -> bool {
+        self.changed_tick::<C>()
+            .is_some_and(|changed| changed > since)
+    }
+
+    /// Returns the raw bytes of a component that was defined at runtime
+    pub fn get_component_bytes(&self, component_id: ComponentId) -> Option<&'a [u8]> {
+        let index = self.archetype.layout().index_of(component_id)?;
+        let location = &self.locations[index];
+
+        let unknown_storage = *self.components.get(&component_id)?;
+        let storage = unknown_storage.as_any().downcast_ref::<BlobStorage>()?;
+
+        storage.get_bytes(location.component())
+    }
 }
 
 pub struct EntryMut<'a> {
     entity: &'a Entity,
-    archetypes: &'a mut ArchetypeStorage,
-    components: &'a mut ComponentStorages,
-    locations: &'a mut LocationMap,
+    world: &'a mut World,
 }
 
 impl<'a> EntryMut<'a> {
-    pub fn new(
-        entity: &'a Entity,
-        archetypes: &'a mut ArchetypeStorage,
-        components: &'a mut ComponentStorages,
-        locations: &'a mut LocationMap,
-    ) -> Self {
-        Self {
-            entity,
-            archetypes,
-            components,
-            locations,
-        }
+    pub fn new(entity: &'a Entity, world: &'a mut World) -> Self {
+        Self { entity, world }
     }
 
     pub fn entity(&self) -> &'a Entity {
@@ -89,60 +104,245 @@ impl<'a> EntryMut<'a> {
     }
 
     pub fn archetype(&self) -> &Archetype {
-        self.archetypes.find_from_entity(self.entity).unwrap()
+        self.world
+            .archetypes
+            .find_from_entity(self.entity)
+            .expect("Entity has no archetype!")
     }
 
     // Trys to get Component `C` from entity
-    pub fn get_component<C: Component>(&mut self) -> Option<&C> {
-        let entity = self.entity();
-        let archetype = self.archetype();
+    pub fn get_component<C: Component>(&self) -> Option<&C> {
+        let index = self.archetype().layout().index_of(ComponentId::of::<C>())?;
 
-        let type_id = TypeId::of::<C>();
-        let index = archetype
-            .layout()
-            .clone()
-            .into_iter()
-            .position(|component_id| component_id == type_id)
-            .unwrap();
-
-        let location = &self.locations.get(entity)[index];
-        let storage = self.components.get_storage::<C>();
+        let location = &self.locations()[index];
+        let storage = self.world.components.try_get_storage::<C>().ok()?;
 
         storage.get_component(location.component())
     }
 
-    // Adds a component to an entity
-    pub fn add_component<C: Component>(&mut self, component: C) {
-        let component_locations = self.locations.get_mut(self.entity);
+    /// Trys to get Component `C` mutable from entity, this marks the component as changed
+    pub fn get_component_mut<C: Component>(&mut self) -> Option<&mut C> {
+        self.world.get_component_mut::<C>(self.entity)
+    }
+
+    /// Returns component `C` of this entity and of another entity mutable at the same time
+    /// Fails if the other entity is this entity, does not exist or one of them does not have the component
+    pub fn get_pair_mut<C: Component>(&mut self, other: &Entity) -> Result<[&mut C; 2], EcsError> {
+        self.world.get_many_mut::<C, 2>([*self.entity, *other])
+    }
+
+    /// Runs `f` on component `C` and marks it as changed, returns None if the entity does not have it
+    pub fn modify<C: Component, R>(&mut self, f: impl FnOnce(&mut C) -> R) -> Option<R> {
+        self.get_component_mut::<C>().map(f)
+    }
+
+    /// Like `get_component` but tells which entity and component are missing
+    pub fn try_get_component<C: Component>(&self) -> Result<&C, EcsError> {
+        self.get_component::<C>().ok_or(EcsError::MissingComponent {
+            entity: *self.entity,
+            component: type_name::<C>(),
+        })
+    }
+
+    /// Returns the raw bytes of a component that was defined at runtime
+    pub fn get_component_bytes(&self, component_id: ComponentId) -> Option<&[u8]> {
+        let index = self.archetype().layout().index_of(component_id)?;
+        let location = &self.locations()[index];
+
+        let unknown_storage = self.world.components.get_storage_raw(component_id)?;
+        let storage = unknown_storage.as_any().downcast_ref::<BlobStorage>()?;
+
+        storage.get_bytes(location.component())
+    }
+
+    /// Returns the raw bytes of a component that was defined at runtime mutable and marks it as changed
+    pub fn get_component_bytes_mut(&mut self, component_id: ComponentId) -> Option<&mut [u8]> {
+        let index = self.archetype().layout().index_of(component_id)?;
+
+        let change_tick = self.world.change_tick();
+        let location = &mut self.locations_mut()[index];
+        location.set_changed(change_tick);
+        let location = location.component();
+
+        let unknown_storage = self.world.components.storage_raw_mut(component_id)?;
+        let storage = unknown_storage.as_any_mut().downcast_mut::<BlobStorage>()?;
+
+        storage.get_bytes_mut(location)
+    }
+
+    // Adds a component to an entity and runs its add hooks
+    // If the entity already has `C` the value is replaced in place without moving the entity
+    // to another archetype, the replace hooks run and the old value is returned
+    pub fn add_component<C: Component>(&mut self, component: C) -> Option<C> {
+        let component_id = ComponentId::of::<C>();
+
+        if let Some(current) = self.get_component_mut::<C>() {
+            let old = mem::replace(current, component);
+            self.world
+                .trigger_hooks(HookKind::Replace, component_id, self.entity);
+
+            return Some(old);
+        }
 
+        let storage = self.world.components.storage_mut::<C>();
+        let storage_index = storage.push_component(component);
+
+        // Required components the entity does not have yet are added with the same archetype move
+        let mut new_layout = self.archetype().layout().clone();
+        new_layout.register_component_id(component_id);
+        let mut added = vec![(component_id, storage_index)];
+        added.extend(
+            RequiredComponents::of::<C>()
+                .insert_missing(&mut new_layout, &mut self.world.components),
+        );
+
+        let archetype_index = self.move_to_layout(new_layout);
+
+        let change_tick = self.world.change_tick();
+        for (_, storage_index) in &added {
+            let location = EntityLocation::new(archetype_index, *storage_index, change_tick);
+            self.locations_mut().push(location);
+        }
+
+        for (component_id, _) in added {
+            self.world
+                .trigger_hooks(HookKind::Add, component_id, self.entity);
+        }
+
+        None
+    }
+
+    /// Adds a component by its id, this is how components that were defined at runtime are added
+    /// An existing value of the component is dropped and replaced in place
+    /// Fails if the storage of the component does not exist, the value is not moved in that case
+    ///
+    /// # Safety
+    /// `component` has to point to a valid value of the component,
+    /// the value is moved into the storage and must not be used or dropped afterwards
+    /// The value has to be safe to send to and share between threads, because the world is `Send` and `Sync`
+    pub unsafe fn add_component_by_id(
+        &mut self,
+        component_id: ComponentId,
+        component: NonNull<u8>,
+    ) -> Result<(), EcsError> {
+        let index = self.archetype().layout().index_of(component_id);
+        let change_tick = self.world.change_tick();
+
+        let storage = self
+            .world
+            .components
+            .storage_raw_mut(component_id)
+            .ok_or(EcsError::MissingStorage(component_id))?;
+
+        if let Some(index) = index {
+            let location = &mut self
+                .world
+                .locations
+                .get_mut(self.entity)
+                .expect("Entity has no locations!")[index];
+            location.set_changed(change_tick);
+            storage.replace_ptr(location.component(), component);
+
+            self.world
+                .trigger_hooks(HookKind::Replace, component_id, self.entity);
+
+            return Ok(());
+        }
+
+        let storage_index = storage.push_ptr(component);
+
+        let archetype_index = self.extend_layout(component_id);
+
+        let location =
+            EntityLocation::new(archetype_index, storage_index, self.world.change_tick());
+        self.locations_mut().push(location);
+
+        self.world
+            .trigger_hooks(HookKind::Add, component_id, self.entity);
+
+        Ok(())
+    }
+
+    /// Removes component `C` from the entity, returns false if the entity did not have it
+    /// The value stays in its storage until the storage is cleaned up
+    pub fn remove_component<C: Component>(&mut self) -> bool {
+        self.remove_component_by_id(ComponentId::of::<C>())
+    }
+
+    /// Removes a component by its id, returns false if the entity did not have it
+    /// The remove hooks run before the component is removed so they can still read it
+    pub fn remove_component_by_id(&mut self, component_id: ComponentId) -> bool {
+        if !self.archetype().layout().contains(component_id) {
+            return false;
+        }
+
+        self.world
+            .trigger_hooks(HookKind::Remove, component_id, self.entity);
+
+        // Hooks may have changed the entity, so the layout is read again
+        let mut new_layout = self.archetype().layout().clone();
+        let Some(index) = new_layout.index_of(component_id) else {
+            return false;
+        };
+        new_layout.unregister_component_id(component_id);
+
+        self.move_to_layout(new_layout);
+        self.locations_mut().remove(index);
+
+        true
+    }
+
+    fn locations(&self) -> &[EntityLocation] {
+        self.world
+            .locations
+            .get(self.entity)
+            .expect("Entity has no locations!")
+    }
+
+    fn locations_mut(&mut self) -> &mut Vec<EntityLocation> {
+        self.world
+            .locations
+            .get_mut(self.entity)
+            .expect("Entity has no locations!")
+    }
+
+    // Moves the entity into the archetype of its layout extended by a component
+    fn extend_layout(&mut self, component_id: ComponentId) -> ArchetypeIndex {
+        let mut new_layout = self.archetype().layout().clone();
+        new_layout.register_component_id(component_id);
+
+        self.move_to_layout(new_layout)
+    }
+
+    // Moves the entity into the archetype with the provided layout
+    fn move_to_layout(&mut self, new_layout: EntityLayout) -> ArchetypeIndex {
         // Get archetype that the entity is assigned to
         let current_archetype = self
+            .world
             .archetypes
             .find_from_entity_mut(self.entity)
             .expect("Entity has no archetype!");
 
-        // Create new layout for entity
-        let mut new_layout = current_archetype.layout().clone();
-        new_layout.register_component::<C>();
-
         current_archetype.unassigne_entity(self.entity);
 
-        let archetype = match self.archetypes.find_from_layout_mut(&new_layout) {
+        let archetype = match self.world.archetypes.find_from_layout_mut(&new_layout) {
             Some(archetype) => {
                 archetype.assigne_entity(self.entity);
                 archetype
             }
             None => {
-                let archetype = self.archetypes.create_from_layout(new_layout);
+                let archetype = self.world.archetypes.create_from_layout(new_layout);
                 archetype.assigne_entity(self.entity);
                 archetype
             }
         };
+        let archetype_index = archetype.index();
 
-        let storage = self.components.get_storage_mut::<C>();
-        let storage_index = storage.push_component(component);
+        // Keep the locations pointing to the archetype the entity is in now
+        for location in self.locations_mut() {
+            location.set_archetype(archetype_index);
+        }
 
-        let location = EntityLocation::new(archetype.index(), storage_index);
-        component_locations.push(location);
+        archetype_index
     }
 }
@@ -0,0 +1,129 @@
+use std::{error::Error, fmt};
+
+use crate::{archetype::Entity, storage::ComponentId};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EcsError {
+    // Entity was never spawned in this world
+    NoSuchEntity(Entity),
+    // Entity existed but was despawned
+    StaleHandle(Entity),
+    // Entity was passed more than once where distinct entities are required
+    DuplicateEntity(Entity),
+    // Entity exists but does not have the component
+    MissingComponent {
+        entity: Entity,
+        component: &'static str,
+    },
+    // No storage was created for the component yet
+    MissingStorage(ComponentId),
+    // The storage of a component is not of the expected type
+    StorageTypeMismatch {
+        component: &'static str,
+    },
+    // A dynamic component of another world has the same name as one of this world but a different layout or drop function
+    IncompatibleComponent {
+        component: String,
+    },
+    // The storage of a component is borrowed already, `mutably` tells if the existing borrow is mutable
+    StorageBorrowed {
+        component: &'static str,
+        mutably: bool,
+    },
+    // A value that is not `Send` was accessed from another thread than the one that created it
+    WrongThread {
+        value: &'static str,
+    },
+    // Entity would become a child of one of its descendants
+    HierarchyCycle {
+        child: Entity,
+        parent: Entity,
+    },
+    // Systems of a stage are ordered before and after each other
+    SystemOrderCycle {
+        stage: String,
+        systems: Vec<String>,
+    },
+    // A system that has to run on the main thread was run on another thread
+    MainThreadSystem {
+        stage: String,
+        system: String,
+    },
+    // A system is ordered relative to a label no system of its stage has
+    UnknownSystemLabel {
+        stage: String,
+        label: String,
+    },
+}
+
+impl fmt::Display for EcsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoSuchEntity(entity) => write!(f, "entity {} does not exist", entity.id()),
+            Self::StaleHandle(entity) => write!(f, "entity {} was despawned", entity.id()),
+            Self::DuplicateEntity(entity) => write!(f, "entity {} was passed twice", entity.id()),
+            Self::MissingComponent { entity, component } => {
+                write!(f, "entity {} has no component {}", entity.id(), component)
+            }
+            Self::MissingStorage(component_id) => {
+                write!(f, "no storage exists for component {:?}", component_id)
+            }
+            Self::StorageTypeMismatch { component } => {
+                write!(
+                    f,
+                    "storage of component {} has an unexpected type",
+                    component
+                )
+            }
+            Self::IncompatibleComponent { component } => {
+                write!(
+                    f,
+                    "component {} is registered with a different layout or drop function",
+                    component
+                )
+            }
+            Self::StorageBorrowed { component, mutably } => {
+                if *mutably {
+                    write!(f, "component {} is already borrowed mutably", component)
+                } else {
+                    write!(f, "component {} is already borrowed", component)
+                }
+            }
+            Self::WrongThread { value } => {
+                write!(
+                    f,
+                    "{} can only be accessed on the thread that created it",
+                    value
+                )
+            }
+            Self::HierarchyCycle { child, parent } => {
+                write!(
+                    f,
+                    "entity {} can't be a child of its descendant {}",
+                    child.id(),
+                    parent.id()
+                )
+            }
+            Self::SystemOrderCycle { stage, systems } => {
+                write!(
+                    f,
+                    "systems of stage {} are ordered in a cycle: {}",
+                    stage,
+                    systems.join(" -> ")
+                )
+            }
+            Self::MainThreadSystem { stage, system } => {
+                write!(
+                    f,
+                    "{} of stage {} has to run on the main thread",
+                    system, stage
+                )
+            }
+            Self::UnknownSystemLabel { stage, label } => {
+                write!(f, "stage {} has no system labeled {}", stage, label)
+            }
+        }
+    }
+}
+
+impl Error for EcsError {}
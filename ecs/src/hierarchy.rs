@@ -0,0 +1,331 @@
+use std::collections::HashMap;
+
+use crate::{
+    archetype::Entity,
+    error::EcsError,
+    storage::{Component, VecStorage},
+    world::World,
+};
+
+/// Points to the parent of an entity, maintained by the world
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Parent(Entity);
+
+impl Parent {
+    pub fn get(&self) -> Entity {
+        self.0
+    }
+}
+
+impl Component for Parent {
+    type Storage = VecStorage<Self>;
+}
+
+/// Lists the children of an entity, maintained by the world
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Children(Vec<Entity>);
+
+impl Children {
+    pub fn as_slice(&self) -> &[Entity] {
+        &self.0
+    }
+}
+
+impl Component for Children {
+    type Storage = VecStorage<Self>;
+}
+
+impl World {
+    /// Makes `parent` the parent of `child`, a previous parent is replaced
+    /// Panics if an entity does not exist or the child is an ancestor of the parent, which would create a cycle
+    pub fn set_parent(&mut self, child: &Entity, parent: &Entity) {
+        self.try_set_parent(child, parent)
+            .unwrap_or_else(|error| panic!("{}", error));
+    }
+
+    /// Makes `parent` the parent of `child`, a previous parent is replaced
+    /// Fails without changing anything if an entity does not exist or the child is an ancestor of the parent
+    pub fn try_set_parent(&mut self, child: &Entity, parent: &Entity) -> Result<(), EcsError> {
+        self.check_entity(child)?;
+        self.check_entity(parent)?;
+
+        if child == parent || self.ancestors(parent).any(|ancestor| ancestor == *child) {
+            return Err(EcsError::HierarchyCycle {
+                child: *child,
+                parent: *parent,
+            });
+        }
+
+        if self.parent(child) == Some(*parent) {
+            return Ok(());
+        }
+
+        self.remove_parent(child);
+        self.entry_mut(child).add_component(Parent(*parent));
+
+        match self.get_component_mut::<Children>(parent) {
+            Some(children) => children.0.push(*child),
+            None => {
+                self.entry_mut(parent).add_component(Children(vec![*child]));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Detaches an entity from its parent and returns the old parent
+    pub fn remove_parent(&mut self, child: &Entity) -> Option<Entity> {
+        let parent = self.parent(child)?;
+        self.entry_mut(child).remove_component::<Parent>();
+
+        let children = self
+            .get_component_mut::<Children>(&parent)
+            .expect("Parent has no children!");
+        children.0.retain(|entity| entity != child);
+
+        if children.0.is_empty() {
+            self.entry_mut(&parent).remove_component::<Children>();
+        }
+
+        Some(parent)
+    }
+
+    /// Returns the parent of an entity
+    pub fn parent(&self, entity: &Entity) -> Option<Entity> {
+        self.get_component::<Parent>(entity).map(Parent::get)
+    }
+
+    /// Returns the direct children of an entity
+    pub fn children(&self, entity: &Entity) -> &[Entity] {
+        self.get_component::<Children>(entity)
+            .map(Children::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Iterates over the parent, grandparent, ... of an entity
+    pub fn ancestors(&self, entity: &Entity) -> Ancestors<'_> {
+        Ancestors {
+            world: self,
+            current: *entity,
+        }
+    }
+
+    /// Iterates depth first over every entity below an entity
+    pub fn descendants(&self, entity: &Entity) -> Descendants<'_> {
+        let mut stack = self.children(entity).to_vec();
+        stack.reverse();
+
+        Descendants { world: self, stack }
+    }
+
+    // Points parents and children of moved entities to the new handles of the entities
+    pub(crate) fn remap_hierarchy(&mut self, entities: &HashMap<Entity, Entity>) {
+        let remap = |entity: &mut Entity| {
+            if let Some(new_entity) = entities.get(entity) {
+                *entity = *new_entity;
+            }
+        };
+
+        for entity in entities.values() {
+            if let Some(parent) = self.get_component_mut::<Parent>(entity) {
+                remap(&mut parent.0);
+            }
+            if let Some(children) = self.get_component_mut::<Children>(entity) {
+                children.0.iter_mut().for_each(remap);
+            }
+        }
+    }
+
+    /// Despawns an entity together with all of its descendants
+    pub fn despawn_recursive(&mut self, entity: &Entity) {
+        let descendants: Vec<Entity> = self.descendants(entity).collect();
+
+        self.despawn(entity);
+        for descendant in descendants {
+            self.despawn(&descendant);
+        }
+    }
+}
+
+pub struct Ancestors<'a> {
+    world: &'a World,
+    current: Entity,
+}
+
+impl Iterator for Ancestors<'_> {
+    type Item = Entity;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let parent = self.world.parent(&self.current)?;
+        self.current = parent;
+
+        Some(parent)
+    }
+}
+
+pub struct Descendants<'a> {
+    world: &'a World,
+    stack: Vec<Entity>,
+}
+
+impl Iterator for Descendants<'_> {
+    type Item = Entity;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entity = self.stack.pop()?;
+        self.stack
+            .extend(self.world.children(&entity).iter().rev().copied());
+
+        Some(entity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        archetype::Entity,
+        error::EcsError,
+        storage::{Component, VecStorage},
+        world::World,
+    };
+
+    use super::{Children, Parent};
+
+    #[derive(Debug, PartialEq)]
+    struct Transform(f32, f32);
+
+    impl Component for Transform {
+        type Storage = VecStorage<Self>;
+    }
+
+    #[test]
+    fn hierarchy_reparent() {
+        let mut world = World::new();
+
+        let root = world.spawn(Transform(0.0, 0.0));
+        let arm = world.spawn(Transform(1.0, 0.0));
+        let hand = world.spawn(Transform(2.0, 0.0));
+
+        world.set_parent(&arm, &root);
+        world.set_parent(&hand, &arm);
+
+        assert_eq!(world.ancestors(&hand).collect::<Vec<_>>(), vec![arm, root]);
+        assert_eq!(
+            world.descendants(&root).collect::<Vec<_>>(),
+            vec![arm, hand]
+        );
+
+        world.set_parent(&hand, &root);
+
+        assert_eq!(world.children(&root), &[arm, hand]);
+        assert!(world.children(&arm).is_empty());
+        assert_eq!(
+            *world.entry(&arm).get_component::<Transform>().unwrap(),
+            Transform(1.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn hierarchy_despawn_recursive() {
+        let mut world = World::new();
+
+        let root = world.spawn(Transform(0.0, 0.0));
+        let child = world.spawn(Transform(1.0, 0.0));
+        let grandchild = world.spawn(Transform(2.0, 0.0));
+        let other = world.spawn(Transform(3.0, 0.0));
+
+        world.set_parent(&child, &root);
+        world.set_parent(&grandchild, &child);
+        world.set_parent(&other, &root);
+
+        world.despawn_recursive(&child);
+
+        assert_eq!(world.children(&root), &[other]);
+        assert!(!world.despawn(&grandchild));
+        assert_eq!(world.parent(&other), Some(root));
+    }
+
+    #[test]
+    #[should_panic]
+    fn hierarchy_cycle_panics() {
+        let mut world = World::new();
+
+        let parent = world.spawn(Transform(0.0, 0.0));
+        let child = world.spawn(Transform(1.0, 0.0));
+
+        world.set_parent(&child, &parent);
+        world.set_parent(&parent, &child);
+    }
+
+    #[test]
+    fn set_parent_checks_entities_first() {
+        let mut world = World::new();
+
+        let parent = world.spawn(Transform(0.0, 0.0));
+        let child = world.spawn(Transform(1.0, 0.0));
+        world.despawn(&parent);
+
+        assert_eq!(
+            world.try_set_parent(&child, &parent),
+            Err(EcsError::StaleHandle(parent))
+        );
+        assert_eq!(world.parent(&child), None);
+        assert!(world.get_component::<Parent>(&child).is_none());
+        assert_eq!(
+            world.try_set_parent(&child, &child),
+            Err(EcsError::HierarchyCycle {
+                child,
+                parent: child,
+            })
+        );
+    }
+
+    #[test]
+    fn despawn_removes_hierarchy_once() {
+        #[derive(Debug, Default)]
+        struct Removed(Vec<(&'static str, Entity)>);
+
+        let mut world = World::new();
+        world.insert_resource(Removed::default());
+        world.on_remove::<Parent>(|world, entity| {
+            world
+                .resource_mut::<Removed>()
+                .unwrap()
+                .0
+                .push(("parent", entity));
+        });
+        world.on_remove::<Children>(|world, entity| {
+            world
+                .resource_mut::<Removed>()
+                .unwrap()
+                .0
+                .push(("children", entity));
+        });
+        world.on_remove::<Transform>(|world, entity| {
+            world
+                .resource_mut::<Removed>()
+                .unwrap()
+                .0
+                .push(("transform", entity));
+        });
+
+        let root = world.spawn(Transform(0.0, 0.0));
+        let first = world.spawn(Transform(1.0, 0.0));
+        let second = world.spawn(Transform(2.0, 0.0));
+        world.set_parent(&first, &root);
+        world.set_parent(&second, &root);
+
+        world.despawn(&root);
+
+        assert_eq!(
+            world.resource::<Removed>().unwrap().0,
+            vec![
+                ("parent", first),
+                ("parent", second),
+                ("children", root),
+                ("transform", root)
+            ]
+        );
+        assert_eq!(world.parent(&first), None);
+    }
+}
@@ -0,0 +1,168 @@
+use std::{collections::HashMap, fmt, sync::Arc};
+
+use crate::{
+    archetype::Entity,
+    storage::{Component, ComponentId},
+    world::World,
+};
+
+/// Callback that runs when a component of an entity changes
+/// It gets full access to the world to perform follow up changes
+pub type Hook = Arc<dyn Fn(&mut World, Entity) + Send + Sync>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HookKind {
+    // Component was added to an entity that did not have it
+    Add,
+    // Component was added to an entity that already had it
+    Replace,
+    // Component is about to be removed from an entity
+    Remove,
+}
+
+// Holds all hooks registered in a world
+#[derive(Default)]
+pub struct Hooks {
+    components: HashMap<(HookKind, ComponentId), Vec<Hook>>,
+    despawn: Vec<Hook>,
+}
+
+impl Hooks {
+    pub fn new() -> Self {
+        Self {
+            components: HashMap::new(),
+            despawn: Vec::new(),
+        }
+    }
+
+    pub fn register(&mut self, kind: HookKind, component_id: ComponentId, hook: Hook) {
+        self.components
+            .entry((kind, component_id))
+            .or_default()
+            .push(hook);
+    }
+
+    pub fn register_despawn(&mut self, hook: Hook) {
+        self.despawn.push(hook);
+    }
+
+    /// Returns the hooks of a component, the hooks are cloned so they can be run with the world
+    pub fn get(&self, kind: HookKind, component_id: ComponentId) -> Vec<Hook> {
+        self.components
+            .get(&(kind, component_id))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn get_despawn(&self) -> Vec<Hook> {
+        self.despawn.clone()
+    }
+}
+
+impl fmt::Debug for Hooks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Hooks")
+            .field("components", &self.components.len())
+            .field("despawn", &self.despawn.len())
+            .finish()
+    }
+}
+
+impl World {
+    /// Runs `hook` after component `C` was added to an entity that did not have it yet
+    pub fn on_add<C: Component>(
+        &mut self,
+        hook: impl Fn(&mut World, Entity) + Send + Sync + 'static,
+    ) {
+        self.hooks
+            .register(HookKind::Add, ComponentId::of::<C>(), Arc::new(hook));
+    }
+
+    /// Runs `hook` after component `C` was added to an entity that already had it
+    pub fn on_replace<C: Component>(
+        &mut self,
+        hook: impl Fn(&mut World, Entity) + Send + Sync + 'static,
+    ) {
+        self.hooks
+            .register(HookKind::Replace, ComponentId::of::<C>(), Arc::new(hook));
+    }
+
+    /// Runs `hook` before component `C` is removed from an entity, also when the entity is despawned
+    pub fn on_remove<C: Component>(
+        &mut self,
+        hook: impl Fn(&mut World, Entity) + Send + Sync + 'static,
+    ) {
+        self.hooks
+            .register(HookKind::Remove, ComponentId::of::<C>(), Arc::new(hook));
+    }
+
+    /// Runs `hook` before any entity is despawned
+    pub fn on_despawn(&mut self, hook: impl Fn(&mut World, Entity) + Send + Sync + 'static) {
+        self.hooks.register_despawn(Arc::new(hook));
+    }
+
+    /// Runs every hook of a kind registered for a component
+    pub fn trigger_hooks(&mut self, kind: HookKind, component_id: ComponentId, entity: &Entity) {
+        for hook in self.hooks.get(kind, component_id) {
+            hook(self, *entity);
+        }
+    }
+
+    /// Runs every despawn hook
+    pub fn trigger_despawn_hooks(&mut self, entity: &Entity) {
+        for hook in self.hooks.get_despawn() {
+            hook(self, *entity);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::{
+        storage::{Component, VecStorage},
+        world::World,
+    };
+
+    #[derive(Debug, PartialEq)]
+    struct Collider(f32);
+
+    impl Component for Collider {
+        type Storage = VecStorage<Self>;
+    }
+
+    #[derive(Debug, Default)]
+    struct Broadphase(HashSet<u32>);
+
+    impl Component for Broadphase {
+        type Storage = VecStorage<Self>;
+    }
+
+    #[test]
+    fn hooks_maintain_index() {
+        let mut world = World::new();
+        let index = world.spawn(Broadphase::default());
+
+        world.on_add::<Collider>(move |world, entity| {
+            let broadphase = world.get_component_mut::<Broadphase>(&index).unwrap();
+            broadphase.0.insert(entity.id());
+        });
+        world.on_remove::<Collider>(move |world, entity| {
+            assert!(world.get_component::<Collider>(&entity).is_some());
+
+            let broadphase = world.get_component_mut::<Broadphase>(&index).unwrap();
+            broadphase.0.remove(&entity.id());
+        });
+
+        let ball = world.spawn(Collider(1.0));
+        let wall = world.spawn(Collider(10.0));
+        let crate_ = world.spawn(Collider(2.0));
+
+        world.entry_mut(&wall).remove_component::<Collider>();
+        world.despawn(&crate_);
+
+        let broadphase = world.get_component::<Broadphase>(&index).unwrap();
+        assert_eq!(broadphase.0, HashSet::from([ball.id()]));
+    }
+}
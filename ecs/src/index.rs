@@ -0,0 +1,314 @@
+use std::{
+    any::{type_name, Any},
+    collections::{HashMap, HashSet},
+    fmt,
+    hash::Hash,
+    mem,
+};
+
+use crate::{
+    archetype::Entity,
+    query::Query,
+    storage::{Component, ComponentId},
+    world::World,
+};
+
+// Part of an index that can be used without knowing the key type
+trait ErasedIndex: Any + Send + Sync {
+    fn as_any(&self) -> &dyn Any;
+
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+
+    // Remembers that the key of an entity might have been changed through a mutable reference
+    fn mark_dirty(&mut self, entity: Entity);
+
+    // Indexes the current key of every entity marked as dirty
+    fn refresh(&mut self, world: &World);
+
+    // Indexes the current key of every entity again
+    fn rebuild(&mut self, world: &World);
+}
+
+// Maps the values of key component `K` to the entities that have it, in the order they were indexed
+struct Index<K> {
+    entities: HashMap<K, Vec<Entity>>,
+    keys: HashMap<Entity, K>,
+    dirty: HashSet<Entity>,
+}
+
+impl<K: Component + Hash + Eq + Clone> Index<K> {
+    fn new() -> Self {
+        Self {
+            entities: HashMap::new(),
+            keys: HashMap::new(),
+            dirty: HashSet::new(),
+        }
+    }
+
+    fn insert(&mut self, entity: Entity, key: K) {
+        self.remove(entity);
+
+        self.entities.entry(key.clone()).or_default().push(entity);
+        self.keys.insert(entity, key);
+    }
+
+    fn remove(&mut self, entity: Entity) {
+        self.dirty.remove(&entity);
+
+        if let Some(key) = self.keys.remove(&entity) {
+            let entities = self.entities.get_mut(&key).unwrap();
+            entities.retain(|other| *other != entity);
+            if entities.is_empty() {
+                self.entities.remove(&key);
+            }
+        }
+    }
+}
+
+impl<K: Component + Hash + Eq + Clone> ErasedIndex for Index<K> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn mark_dirty(&mut self, entity: Entity) {
+        self.dirty.insert(entity);
+    }
+
+    fn refresh(&mut self, world: &World) {
+        for entity in mem::take(&mut self.dirty) {
+            match world.get_component::<K>(&entity) {
+                Some(key) => self.insert(entity, key.clone()),
+                None => self.remove(entity),
+            }
+        }
+    }
+
+    fn rebuild(&mut self, world: &World) {
+        *self = Self::new();
+
+        let query = Query::builder().with::<K>().build();
+        for entity in world.query_entities(&query) {
+            let key = world.get_component::<K>(&entity).unwrap().clone();
+            self.insert(entity, key);
+        }
+    }
+}
+
+// Holds the indexes of every indexed component
+#[derive(Default)]
+pub struct Indexes {
+    indexes: HashMap<ComponentId, Box<dyn ErasedIndex>>,
+}
+
+impl Indexes {
+    pub fn new() -> Self {
+        Self {
+            indexes: HashMap::new(),
+        }
+    }
+
+    pub fn contains(&self, component_id: ComponentId) -> bool {
+        self.indexes.contains_key(&component_id)
+    }
+
+    // Remembers that the component of an entity was accessed mutable, if the component is indexed
+    pub(crate) fn mark_dirty(&mut self, component_id: ComponentId, entity: Entity) {
+        if let Some(index) = self.indexes.get_mut(&component_id) {
+            index.mark_dirty(entity);
+        }
+    }
+
+    fn get<K: Component + Hash + Eq + Clone>(&self) -> Option<&Index<K>> {
+        self.indexes
+            .get(&ComponentId::of::<K>())
+            .map(|index| index.as_any().downcast_ref().unwrap())
+    }
+
+    fn get_mut<K: Component + Hash + Eq + Clone>(&mut self) -> Option<&mut Index<K>> {
+        self.indexes
+            .get_mut(&ComponentId::of::<K>())
+            .map(|index| index.as_any_mut().downcast_mut().unwrap())
+    }
+}
+
+impl fmt::Debug for Indexes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Indexes")
+            .field("indexes", &self.indexes.len())
+            .finish()
+    }
+}
+
+impl World {
+    /// Indexes every entity by the value of its component `K` so it can be found with `lookup`
+    /// Adding, replacing and removing `K` updates the index right away, values that were accessed
+    /// mutable are indexed again by the next refresh
+    /// If the storage of `K` is accessed mutable directly every entity is indexed again by the next refresh
+    pub fn index_by<K: Component + Hash + Eq + Clone>(&mut self) {
+        let component_id = ComponentId::of::<K>();
+        if self.indexes.contains(component_id) {
+            return;
+        }
+
+        let mut index = Index::<K>::new();
+        index.rebuild(self);
+        self.indexes.indexes.insert(component_id, Box::new(index));
+        self.components.set_indexed(component_id);
+
+        self.on_add::<K>(|world, entity| world.reindex::<K>(entity));
+        self.on_replace::<K>(|world, entity| world.reindex::<K>(entity));
+        self.on_remove::<K>(|world, entity| {
+            world.indexes.get_mut::<K>().unwrap().remove(entity);
+        });
+    }
+
+    /// Returns the entity whose component `K` equals `key`, the last indexed one if several share the key
+    /// Values accessed mutable since the last refresh are found by their old key, see `refresh_index`
+    /// Panics if `K` is not indexed
+    pub fn lookup<K: Component + Hash + Eq + Clone>(&self, key: &K) -> Option<Entity> {
+        self.lookup_all(key).last().copied()
+    }
+
+    /// Returns every entity whose component `K` equals `key` in the order they were indexed
+    /// Panics if `K` is not indexed
+    pub fn lookup_all<K: Component + Hash + Eq + Clone>(&self, key: &K) -> &[Entity] {
+        self.indexes
+            .get::<K>()
+            .unwrap_or_else(|| panic!("Component {} is not indexed!", type_name::<K>()))
+            .entities
+            .get(key)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Indexes the values of `K` that were accessed mutable since the last refresh
+    pub fn refresh_index<K: Component + Hash + Eq + Clone>(&mut self) {
+        self.refresh_index_by_id(ComponentId::of::<K>());
+    }
+
+    /// Refreshes every index, this also happens every time the change tick is incremented
+    pub fn refresh_indexes(&mut self) {
+        let component_ids: Vec<ComponentId> = self.indexes.indexes.keys().copied().collect();
+        for component_id in component_ids {
+            self.refresh_index_by_id(component_id);
+        }
+    }
+
+    fn refresh_index_by_id(&mut self, component_id: ComponentId) {
+        let Some(mut index) = self.indexes.indexes.remove(&component_id) else {
+            return;
+        };
+
+        if self.components.take_touched(component_id) {
+            index.rebuild(self);
+        } else {
+            index.refresh(self);
+        }
+        self.indexes.indexes.insert(component_id, index);
+    }
+
+    fn reindex<K: Component + Hash + Eq + Clone>(&mut self, entity: Entity) {
+        let key = self.get_component::<K>(&entity).unwrap().clone();
+        self.indexes.get_mut::<K>().unwrap().insert(entity, key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        storage::{Component, Storage, VecStorage},
+        world::World,
+    };
+
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    struct NetworkId(u64);
+
+    impl Component for NetworkId {
+        type Storage = VecStorage<Self>;
+    }
+
+    #[test]
+    fn lookup_follows_changes() {
+        let mut world = World::new();
+        let host = world.spawn(NetworkId(1));
+        world.index_by::<NetworkId>();
+
+        let client = world.spawn(NetworkId(2));
+        let bot = world.spawn(NetworkId(3));
+        assert_eq!(world.lookup(&NetworkId(1)), Some(host));
+        assert_eq!(world.lookup(&NetworkId(2)), Some(client));
+
+        // Mutated keys are found by their old value until the index is refreshed
+        world.get_component_mut::<NetworkId>(&client).unwrap().0 = 20;
+        assert_eq!(world.lookup(&NetworkId(2)), Some(client));
+        world.refresh_index::<NetworkId>();
+        assert_eq!(world.lookup(&NetworkId(2)), None);
+        assert_eq!(world.lookup(&NetworkId(20)), Some(client));
+
+        world.get_component_mut::<NetworkId>(&bot).unwrap().0 = 30;
+        world.increment_change_tick();
+        assert_eq!(world.lookup(&NetworkId(30)), Some(bot));
+        world.get_component_mut::<NetworkId>(&bot).unwrap().0 = 3;
+        world.refresh_indexes();
+
+        world.entry_mut(&host).add_component(NetworkId(10));
+        world.entry_mut(&bot).remove_component::<NetworkId>();
+        world.despawn(&client);
+
+        assert_eq!(world.lookup(&NetworkId(1)), None);
+        assert_eq!(world.lookup(&NetworkId(10)), Some(host));
+        assert_eq!(world.lookup(&NetworkId(3)), None);
+        assert_eq!(world.lookup(&NetworkId(20)), None);
+    }
+
+    #[test]
+    fn lookup_follows_storage_access() {
+        let mut world = World::new();
+        let host = world.spawn(NetworkId(1));
+        let client = world.spawn(NetworkId(2));
+        world.index_by::<NetworkId>();
+
+        let storage = world.components.get_storage_mut::<NetworkId>();
+        for index in 0..storage.size() {
+            storage.get_component_mut(index).unwrap().0 += 10;
+        }
+        world.refresh_index::<NetworkId>();
+        assert_eq!(world.lookup(&NetworkId(1)), None);
+        assert_eq!(world.lookup(&NetworkId(11)), Some(host));
+
+        let storages = world.components.borrows();
+        storages
+            .borrow_mut::<NetworkId>()
+            .get_component_mut(1)
+            .unwrap()
+            .0 = 30;
+        drop(storages);
+        world.refresh_index::<NetworkId>();
+        assert_eq!(world.lookup(&NetworkId(12)), None);
+        assert_eq!(world.lookup(&NetworkId(30)), Some(client));
+    }
+
+    #[test]
+    fn shared_keys_keep_every_entity() {
+        let mut world = World::new();
+        world.index_by::<NetworkId>();
+        let first = world.spawn(NetworkId(1));
+        let second = world.spawn(NetworkId(1));
+        let third = world.spawn(NetworkId(1));
+
+        let shared = |world: &World| world.lookup(&NetworkId(1));
+        assert_eq!(world.lookup_all(&NetworkId(1)), &[first, second, third]);
+        assert_eq!(shared(&world), Some(third));
+
+        world.despawn(&third);
+        assert_eq!(shared(&world), Some(second));
+        world.entry_mut(&first).remove_component::<NetworkId>();
+        assert_eq!(world.lookup_all(&NetworkId(1)), &[second]);
+        world.despawn(&second);
+        assert_eq!(shared(&world), None);
+    }
+}
@@ -1,6 +1,26 @@
 pub mod archetype;
+pub mod borrow;
+pub mod chunk;
+pub mod command;
+pub mod compact;
+pub mod condition;
 pub mod entry;
+pub mod error;
+pub mod hierarchy;
+pub mod hooks;
+pub mod index;
 pub mod location;
+pub mod multi;
+pub mod non_send;
+pub mod prefab;
 pub mod query;
+pub mod registry;
+pub mod required;
+pub mod resource;
+pub mod schedule;
+pub mod sort;
+pub mod stats;
 pub mod storage;
+pub mod time;
+pub mod transfer;
 pub mod world;
@@ -5,12 +5,19 @@ use crate::{
     storage::ComponentIndex,
 };
 
+/// Counts up every time the world advances, used to detect changed components
+pub type Tick = u32;
+
 #[derive(Debug)]
-pub struct EntityLocation(pub ArchetypeIndex, pub ComponentIndex);
+pub struct EntityLocation(pub ArchetypeIndex, pub ComponentIndex, pub Tick);
 
 impl EntityLocation {
-    pub fn new(archetype_index: ArchetypeIndex, component_index: ComponentIndex) -> Self {
-        Self(archetype_index, component_index)
+    pub fn new(
+        archetype_index: ArchetypeIndex,
+        component_index: ComponentIndex,
+        tick: Tick,
+    ) -> Self {
+        Self(archetype_index, component_index, tick)
     }
 
     pub fn archetype(&self) -> ArchetypeIndex {
@@ -20,10 +27,27 @@ impl EntityLocation {
     pub fn component(&self) -> ComponentIndex {
         self.1
     }
+
+    pub fn set_archetype(&mut self, archetype_index: ArchetypeIndex) {
+        self.0 = archetype_index;
+    }
+
+    pub fn set_component(&mut self, component_index: ComponentIndex) {
+        self.1 = component_index;
+    }
+
+    /// Tick at which the component was added or last accessed mutably
+    pub fn changed(&self) -> Tick {
+        self.2
+    }
+
+    pub fn set_changed(&mut self, tick: Tick) {
+        self.2 = tick;
+    }
 }
 
 // Stores the Storage Location of an entity's data
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct LocationMap {
     locations: HashMap<Entity, Vec<EntityLocation>>,
 }
@@ -35,15 +59,35 @@ impl LocationMap {
         }
     }
 
-    pub fn get(&self, entity: &Entity) -> &[EntityLocation] {
-        self.locations.get(&entity).unwrap()
+    pub fn get(&self, entity: &Entity) -> Option<&[EntityLocation]> {
+        self.locations.get(entity).map(Vec::as_slice)
     }
 
-    pub fn get_mut(&mut self, entity: &Entity) -> &mut Vec<EntityLocation> {
-        self.locations.get_mut(&entity).unwrap()
+    pub fn get_mut(&mut self, entity: &Entity) -> Option<&mut Vec<EntityLocation>> {
+        self.locations.get_mut(entity)
     }
 
     pub fn insert(&mut self, entity: Entity, component_indecies: Vec<EntityLocation>) {
         self.locations.insert(entity, component_indecies);
     }
+
+    pub fn remove(&mut self, entity: &Entity) -> Option<Vec<EntityLocation>> {
+        self.locations.remove(entity)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Entity, &Vec<EntityLocation>)> {
+        self.locations.iter()
+    }
+
+    pub fn contains(&self, entity: &Entity) -> bool {
+        self.locations.contains_key(entity)
+    }
+
+    pub fn len(&self) -> usize {
+        self.locations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.locations.is_empty()
+    }
 }
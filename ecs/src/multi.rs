@@ -0,0 +1,173 @@
+use crate::{
+    archetype::Entity,
+    entry::{EntryMut, EntryRef},
+    query::{Query, QueryBuilder},
+    storage::{Component, VecStorage},
+    world::World,
+};
+
+/// Holds every instance of component `C` an entity carries
+/// An entity has it as soon as the first instance is added and loses it when the last one is removed
+#[derive(Debug, Clone, PartialEq)]
+pub struct Multi<C: Component>(Vec<C>);
+
+impl<C: Component> Multi<C> {
+    pub fn as_slice(&self) -> &[C] {
+        &self.0
+    }
+}
+
+impl<C: Component> Component for Multi<C> {
+    type Storage = VecStorage<Self>;
+}
+
+impl<'a> EntryRef<'a> {
+    /// Returns every instance of component `C`
+    pub fn instances<C: Component>(&self) -> &'a [C] {
+        self.get_component::<Multi<C>>()
+            .map(Multi::as_slice)
+            .unwrap_or_default()
+    }
+}
+
+impl EntryMut<'_> {
+    /// Returns every instance of component `C`
+    pub fn instances<C: Component>(&self) -> &[C] {
+        self.get_component::<Multi<C>>()
+            .map(Multi::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Returns every instance of component `C` mutable and marks them as changed
+    pub fn instances_mut<C: Component>(&mut self) -> &mut [C] {
+        self.get_component_mut::<Multi<C>>()
+            .map(|multi| multi.0.as_mut_slice())
+            .unwrap_or_default()
+    }
+
+    /// Adds another instance of component `C` and returns its index
+    pub fn add_instance<C: Component>(&mut self, component: C) -> usize {
+        match self.get_component_mut::<Multi<C>>() {
+            Some(multi) => {
+                multi.0.push(component);
+                multi.0.len() - 1
+            }
+            None => {
+                self.add_component(Multi(vec![component]));
+                0
+            }
+        }
+    }
+
+    /// Removes the instance of component `C` with a given index
+    /// The indices of the following instances shift down by one
+    pub fn remove_instance<C: Component>(&mut self, index: usize) -> Option<C> {
+        let multi = self.get_component_mut::<Multi<C>>()?;
+        if index >= multi.0.len() {
+            return None;
+        }
+
+        let component = multi.0.remove(index);
+        if multi.0.is_empty() {
+            self.remove_component::<Multi<C>>();
+        }
+
+        Some(component)
+    }
+}
+
+impl QueryBuilder {
+    /// Matches entities that carry at least one instance of component `C`
+    pub fn with_instances<C: Component>(self) -> QueryBuilder {
+        self.with::<Multi<C>>()
+    }
+}
+
+impl World {
+    /// Iterates over every instance of component `C` together with the entity carrying it
+    pub fn query_instances<C: Component>(&self) -> impl Iterator<Item = (Entity, &C)> {
+        let query = Query::builder().with_instances::<C>().build();
+
+        self.query_entities(&query)
+            .into_iter()
+            .flat_map(move |entity| {
+                self.get_component::<Multi<C>>(&entity)
+                    .map(Multi::as_slice)
+                    .unwrap_or_default()
+                    .iter()
+                    .map(move |component| (entity, component))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        storage::{Component, VecStorage},
+        world::World,
+    };
+
+    #[derive(Debug, PartialEq)]
+    struct Name(&'static str);
+
+    impl Component for Name {
+        type Storage = VecStorage<Self>;
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Hitbox(f32);
+
+    impl Component for Hitbox {
+        type Storage = VecStorage<Self>;
+    }
+
+    #[test]
+    fn multi_add_and_remove_instances() {
+        let mut world = World::new();
+        let boss = world.spawn(Name("boss"));
+
+        let mut entry = world.entry_mut(&boss);
+        entry.add_instance(Hitbox(1.0));
+        entry.add_instance(Hitbox(2.0));
+        entry.add_instance(Hitbox(3.0));
+
+        assert_eq!(entry.remove_instance::<Hitbox>(1), Some(Hitbox(2.0)));
+        entry.instances_mut::<Hitbox>()[0].0 = 4.0;
+
+        assert_eq!(
+            world.entry(&boss).instances::<Hitbox>(),
+            &[Hitbox(4.0), Hitbox(3.0)]
+        );
+
+        let mut entry = world.entry_mut(&boss);
+        entry.remove_instance::<Hitbox>(0);
+        entry.remove_instance::<Hitbox>(0);
+
+        assert!(entry.instances::<Hitbox>().is_empty());
+        assert_eq!(entry.archetype().layout().clone().into_iter().count(), 1);
+    }
+
+    #[test]
+    fn multi_query_yields_each_instance() {
+        let mut world = World::new();
+
+        let boss = world.spawn(Name("boss"));
+        let minion = world.spawn(Name("minion"));
+        world.spawn(Name("npc"));
+
+        world.entry_mut(&boss).add_instance(Hitbox(1.0));
+        world.entry_mut(&boss).add_instance(Hitbox(2.0));
+        world.entry_mut(&minion).add_instance(Hitbox(0.5));
+
+        let mut hitboxes: Vec<_> = world
+            .query_instances::<Hitbox>()
+            .map(|(entity, hitbox)| (entity.id(), hitbox.0))
+            .collect();
+        hitboxes.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        assert_eq!(
+            hitboxes,
+            vec![(boss.id(), 1.0), (boss.id(), 2.0), (minion.id(), 0.5)]
+        );
+    }
+}
@@ -0,0 +1,172 @@
+use std::{
+    any::type_name,
+    fmt::{self, Debug},
+    mem::ManuallyDrop,
+    ops::{Deref, DerefMut},
+    thread::{self, ThreadId},
+};
+
+use crate::{
+    error::EcsError,
+    storage::{Component, VecStorage},
+};
+
+/// Wraps a value that is not `Send` or `Sync` so it can be stored as a component
+/// Only the thread that created the wrapper can access the value, other threads get an error or panic
+/// If the wrapper is dropped on another thread the value is leaked instead of dropped
+pub struct NonSend<T: 'static> {
+    value: ManuallyDrop<T>,
+    thread: ThreadId,
+}
+
+// Safety: The wrapper can be moved and shared between threads, but the value itself never is
+// `try_get`, `try_get_mut` and `into_inner` return the value only after `check_thread` confirmed that
+// the current thread created the wrapper, every other accessor goes through them
+// `Drop` only drops the value on that thread and leaks it anywhere else
+// Thread ids are never reused, so no other thread can pass the check after the creating thread exited
+unsafe impl<T: 'static> Send for NonSend<T> {}
+unsafe impl<T: 'static> Sync for NonSend<T> {}
+
+impl<T: 'static> NonSend<T> {
+    /// Wraps a value, it stays bound to the current thread
+    pub fn new(value: T) -> Self {
+        Self {
+            value: ManuallyDrop::new(value),
+            thread: thread::current().id(),
+        }
+    }
+
+    /// Returns the thread the value is bound to
+    pub fn thread(&self) -> ThreadId {
+        self.thread
+    }
+
+    /// Checks if the value can be accessed on the current thread
+    pub fn is_accessible(&self) -> bool {
+        self.thread == thread::current().id()
+    }
+
+    /// Returns a reference to the value
+    /// Panics if called on another thread than the one that created the wrapper
+    pub fn get(&self) -> &T {
+        self.try_get().unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Returns a mutable reference to the value
+    /// Panics if called on another thread than the one that created the wrapper
+    pub fn get_mut(&mut self) -> &mut T {
+        self.try_get_mut()
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_get(&self) -> Result<&T, EcsError> {
+        self.check_thread()?;
+        Ok(&self.value)
+    }
+
+    pub fn try_get_mut(&mut self) -> Result<&mut T, EcsError> {
+        self.check_thread()?;
+        Ok(&mut self.value)
+    }
+
+    /// Unwraps the value
+    /// Panics if called on another thread than the one that created the wrapper
+    pub fn into_inner(self) -> T {
+        self.check_thread()
+            .unwrap_or_else(|error| panic!("{}", error));
+
+        let mut wrapper = ManuallyDrop::new(self);
+        // Safety: The wrapper is never dropped so the value is only taken once
+        unsafe { ManuallyDrop::take(&mut wrapper.value) }
+    }
+
+    fn check_thread(&self) -> Result<(), EcsError> {
+        if self.is_accessible() {
+            Ok(())
+        } else {
+            Err(EcsError::WrongThread {
+                value: type_name::<T>(),
+            })
+        }
+    }
+}
+
+impl<T: 'static> Deref for NonSend<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.get()
+    }
+}
+
+impl<T: 'static> DerefMut for NonSend<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.get_mut()
+    }
+}
+
+impl<T: 'static> Drop for NonSend<T> {
+    fn drop(&mut self) {
+        if self.is_accessible() {
+            // Safety: The value is not used after it was dropped
+            unsafe { ManuallyDrop::drop(&mut self.value) }
+        }
+    }
+}
+
+impl<T: Debug + 'static> Debug for NonSend<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.try_get() {
+            Ok(value) => f.debug_tuple("NonSend").field(value).finish(),
+            Err(_) => f
+                .debug_struct("NonSend")
+                .field("thread", &self.thread)
+                .finish_non_exhaustive(),
+        }
+    }
+}
+
+impl<T: Debug + 'static> Component for NonSend<T> {
+    type Storage = VecStorage<Self>;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{any::type_name, rc::Rc, thread};
+
+    use crate::{error::EcsError, world::World};
+
+    use super::NonSend;
+
+    #[test]
+    fn non_send_components_stay_on_their_thread() {
+        let mut world = World::new();
+        let window = world.spawn(NonSend::new(Rc::new("window")));
+        assert_eq!(
+            **world
+                .get_component::<NonSend<Rc<&str>>>(&window)
+                .unwrap()
+                .get(),
+            "window"
+        );
+
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                let handle = world.get_component::<NonSend<Rc<&str>>>(&window).unwrap();
+                assert!(!handle.is_accessible());
+                assert_eq!(
+                    handle.try_get().err(),
+                    Some(EcsError::WrongThread {
+                        value: type_name::<Rc<&str>>(),
+                    })
+                );
+            });
+        });
+
+        let handle = NonSend::new(Rc::new("cursor"));
+        assert_eq!(*handle.into_inner(), "cursor");
+        assert!(world
+            .entry_mut(&window)
+            .remove_component::<NonSend<Rc<&str>>>());
+    }
+}
@@ -0,0 +1,312 @@
+use std::{
+    alloc::{self, Layout},
+    fmt,
+    ptr::{self, NonNull},
+};
+
+use crate::{
+    archetype::{Entity, EntityLayout},
+    hooks::HookKind,
+    required::RequiredComponents,
+    storage::{Component, ComponentId, ComponentIndex, ComponentStorages, Storage},
+    world::World,
+};
+
+// Pushes a copy of a stored component into its storage
+type Insert = Box<dyn Fn(&mut ComponentStorages) -> ComponentIndex + Send + Sync>;
+
+// Stored component together with the components it requires
+struct PrefabComponent {
+    id: ComponentId,
+    insert: Insert,
+    required: fn() -> RequiredComponents,
+}
+
+/// Stored set of component values that entities can be created from any number of times
+#[derive(Default)]
+pub struct Prefab {
+    components: Vec<PrefabComponent>,
+}
+
+impl Prefab {
+    pub fn new() -> Self {
+        Self {
+            components: Vec::new(),
+        }
+    }
+
+    /// Adds a component, a component of the same type that was added before is replaced
+    pub fn with<C: Component + Clone>(mut self, component: C) -> Self {
+        self.insert(component);
+        self
+    }
+
+    /// Adds a component, a component of the same type that was added before is replaced
+    pub fn insert<C: Component + Clone>(&mut self, component: C) {
+        let prefab_component = PrefabComponent {
+            id: ComponentId::of::<C>(),
+            insert: Box::new(move |storages| {
+                storages
+                    .storage_mut::<C>()
+                    .push_component(component.clone())
+            }),
+            required: RequiredComponents::of::<C>,
+        };
+
+        match self.get(prefab_component.id) {
+            Some(index) => self.components[index] = prefab_component,
+            None => self.components.push(prefab_component),
+        }
+    }
+
+    pub fn contains<C: Component>(&self) -> bool {
+        self.get(ComponentId::of::<C>()).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.components.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.components.is_empty()
+    }
+
+    // Returns the position of a component in the prefab
+    fn get(&self, component_id: ComponentId) -> Option<usize> {
+        self.components
+            .iter()
+            .position(|component| component.id == component_id)
+    }
+}
+
+impl fmt::Debug for Prefab {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Prefab")
+            .field("components", &self.components.len())
+            .finish()
+    }
+}
+
+impl World {
+    /// Creates a new entity with a copy of every component of a prefab
+    pub fn instantiate(&mut self, prefab: &Prefab) -> Entity {
+        self.instantiate_with(prefab, &Prefab::new())
+    }
+
+    /// Creates a new entity from a prefab, components of `overrides` are used instead of the ones of the prefab
+    /// Components only `overrides` has are added as well
+    /// Every component and its required components are inserted at once, so the entity is only placed in its final archetype
+    pub fn instantiate_with(&mut self, prefab: &Prefab, overrides: &Prefab) -> Entity {
+        // Overrides take the place of the prefab component so the layout order stays the same
+        let components = prefab
+            .components
+            .iter()
+            .map(|component| {
+                overrides
+                    .get(component.id)
+                    .map_or(component, |index| &overrides.components[index])
+            })
+            .chain(
+                overrides
+                    .components
+                    .iter()
+                    .filter(|component| prefab.get(component.id).is_none()),
+            );
+
+        let mut layout = EntityLayout::new();
+        let mut added = Vec::new();
+        let mut required = Vec::new();
+        for component in components {
+            layout.register_component_id(component.id);
+            added.push((component.id, (component.insert)(&mut self.components)));
+            required.push(component.required);
+        }
+
+        // Explicit values are in the layout already, so only missing requirements are created
+        for required in required {
+            added.extend(required().insert_missing(&mut layout, &mut self.components));
+        }
+
+        let entity = self.alloc_entity();
+        self.insert_moved(
+            entity,
+            layout,
+            added.iter().map(|(_, index)| *index).collect(),
+        );
+
+        for (component_id, _) in added {
+            self.trigger_hooks(HookKind::Add, component_id, &entity);
+        }
+
+        entity
+    }
+
+    /// Creates a new entity with a copy of every component whose type registered a clone function
+    /// Other components are left out
+    /// Panics if the entity does not exist
+    pub fn clone_entity(&mut self, entity: &Entity) -> Entity {
+        let layout = self.entry(entity).archetype().layout().clone();
+        let clone = self.spawn_empty();
+
+        for (position, component_id) in layout.into_iter().enumerate() {
+            let Some(registration) = self.registry.get(component_id) else {
+                continue;
+            };
+            let Some(clone_fn) = registration.clone_fn() else {
+                continue;
+            };
+            let align = registration.layout().align();
+
+            let index = self.locations.get(entity).unwrap()[position].component();
+            let storage = self
+                .components
+                .get_storage_raw(component_id)
+                .expect("Component has no storage!");
+            let src = storage.get_ptr(index).expect("Component is out of bounds!");
+
+            // The buffer has to fit the component the storage holds, whatever was registered
+            let layout = Layout::from_size_align(storage.item_size(), align).unwrap();
+
+            unsafe {
+                let dst = alloc_buffer(layout);
+                clone_fn(src, dst);
+
+                // Safety: The clone is valid and moved into the storage, only the buffer is freed afterwards
+                self.entry_mut(&clone)
+                    .add_component_by_id(component_id, dst)
+                    .unwrap();
+                dealloc_buffer(dst, layout);
+            }
+        }
+
+        clone
+    }
+}
+
+// Allocates memory for a single value with the layout, zero sized values get a dangling pointer
+unsafe fn alloc_buffer(layout: Layout) -> NonNull<u8> {
+    if layout.size() == 0 {
+        return NonNull::new(ptr::without_provenance_mut(layout.align())).unwrap();
+    }
+
+    NonNull::new(alloc::alloc(layout)).unwrap_or_else(|| alloc::handle_alloc_error(layout))
+}
+
+unsafe fn dealloc_buffer(buffer: NonNull<u8>, layout: Layout) {
+    if layout.size() != 0 {
+        alloc::dealloc(buffer.as_ptr(), layout);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        registry::TypeRegistration,
+        storage::{Component, VecStorage},
+        world::World,
+    };
+
+    use super::Prefab;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Name(String);
+
+    impl Component for Name {
+        type Storage = VecStorage<Self>;
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Velocity(f32);
+
+    impl Component for Velocity {
+        type Storage = VecStorage<Self>;
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Target(u32);
+
+    impl Component for Target {
+        type Storage = VecStorage<Self>;
+    }
+
+    #[test]
+    fn clone_entity_copies_cloneable_components() {
+        let mut world = World::new();
+        world.register(TypeRegistration::of::<Name>("name").with_clone::<Name>());
+        world.register(TypeRegistration::of::<Target>("target"));
+
+        let enemy = world.spawn(Name("goblin".to_string()));
+        world.entry_mut(&enemy).add_component(Velocity(2.0));
+        world.entry_mut(&enemy).add_component(Target(7));
+
+        let clone = world.clone_entity(&enemy);
+        let entry = world.entry(&clone);
+
+        assert_eq!(
+            entry.get_component::<Name>(),
+            Some(&Name("goblin".to_string()))
+        );
+        assert!(entry.get_component::<Velocity>().is_none());
+        assert!(entry.get_component::<Target>().is_none());
+        assert_eq!(
+            world.entry(&enemy).get_component::<Name>(),
+            Some(&Name("goblin".to_string()))
+        );
+    }
+
+    #[test]
+    fn instantiate_prefab_with_overrides() {
+        let mut world = World::new();
+
+        let bullet = Prefab::new()
+            .with(Name("bullet".to_string()))
+            .with(Velocity(1.0))
+            .with(Velocity(10.0));
+        assert_eq!(bullet.len(), 2);
+
+        let first = world.instantiate(&bullet);
+        let fast = world.instantiate_with(
+            &bullet,
+            &Prefab::new()
+                .with(Velocity(50.0))
+                .with(Name("tracer".to_string())),
+        );
+
+        assert_eq!(
+            world.entry(&first).get_component::<Velocity>(),
+            Some(&Velocity(10.0))
+        );
+        assert_eq!(
+            world.entry(&fast).get_component::<Velocity>(),
+            Some(&Velocity(50.0))
+        );
+        assert_eq!(
+            world.entry(&fast).get_component::<Name>(),
+            Some(&Name("tracer".to_string()))
+        );
+        assert_eq!(
+            world.entry(&first).archetype().index(),
+            world.entry(&fast).archetype().index()
+        );
+
+        // Entities are placed in their final archetype right away
+        assert_eq!(world.archetypes.len(), 1);
+    }
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn prefab_can_be_a_resource() {
+        assert_send_sync::<Prefab>();
+
+        let mut world = World::new();
+        world.insert_resource(Prefab::new().with(Velocity(1.0)));
+
+        let prefab = world.remove_resource::<Prefab>().unwrap();
+        let bullet = world.instantiate(&prefab);
+        assert_eq!(
+            world.entry(&bullet).get_component::<Velocity>(),
+            Some(&Velocity(1.0))
+        );
+    }
+}
@@ -1,9 +1,10 @@
-use std::any::TypeId;
-
-use crate::storage::Component;
+use crate::{
+    archetype::EntityLayout,
+    storage::{Component, ComponentId},
+};
 
 pub struct Query {
-    components: Vec<TypeId>,
+    components: Vec<ComponentId>,
 }
 
 impl Query {
@@ -11,14 +12,21 @@ impl Query {
         QueryBuilder::default()
     }
 
-    pub fn components(&self) -> &Vec<TypeId> {
+    pub fn components(&self) -> &Vec<ComponentId> {
         &self.components
     }
+
+    /// Checks if a layout contains every component of the query
+    pub fn matches(&self, layout: &EntityLayout) -> bool {
+        self.components
+            .iter()
+            .all(|component_id| layout.contains(*component_id))
+    }
 }
 
 #[derive(Default)]
 pub struct QueryBuilder {
-    components: Vec<TypeId>,
+    components: Vec<ComponentId>,
 }
 
 impl QueryBuilder {
@@ -29,7 +37,13 @@ impl QueryBuilder {
     }
 
     pub fn with<T: Component>(mut self) -> QueryBuilder {
-        self.components.push(TypeId::of::<T>());
+        self.components.push(ComponentId::of::<T>());
+        self
+    }
+
+    /// Adds a component by its id, used for components that were defined at runtime
+    pub fn with_id(mut self, component_id: ComponentId) -> QueryBuilder {
+        self.components.push(component_id);
         self
     }
 
@@ -0,0 +1,364 @@
+use std::{
+    alloc::Layout,
+    any::{type_name, TypeId},
+    collections::HashMap,
+    fmt::{self, Debug},
+    marker::PhantomData,
+    ptr::NonNull,
+};
+
+use crate::storage::{Component, ComponentId};
+
+/// Drops a component in place
+/// It can be called on any thread, so the values it drops have to be safe to send between threads
+pub type DropFn = unsafe fn(NonNull<u8>);
+
+/// Clones the component behind `src` into the uninitialized memory behind `dst`
+pub type CloneFn = unsafe fn(src: NonNull<u8>, dst: NonNull<u8>);
+
+/// Formats the component behind the pointer
+pub type DebugFn = unsafe fn(NonNull<u8>, &mut fmt::Formatter<'_>) -> fmt::Result;
+
+/// Serializes the component behind the pointer
+pub type SerializeFn = unsafe fn(NonNull<u8>) -> String;
+
+/// Components that can be turned into a string by tooling and scenes
+pub trait SerializeComponent: Component {
+    fn serialize(&self) -> String;
+}
+
+unsafe fn drop_ptr<C>(ptr: NonNull<u8>) {
+    ptr.cast::<C>().as_ptr().drop_in_place()
+}
+
+unsafe fn clone_ptr<C: Clone>(src: NonNull<u8>, dst: NonNull<u8>) {
+    let component = src.cast::<C>().as_ref().clone();
+    dst.cast::<C>().as_ptr().write(component)
+}
+
+unsafe fn debug_ptr<C: Debug>(ptr: NonNull<u8>, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    ptr.cast::<C>().as_ref().fmt(f)
+}
+
+unsafe fn serialize_ptr<C: SerializeComponent>(ptr: NonNull<u8>) -> String {
+    ptr.cast::<C>().as_ref().serialize()
+}
+
+/// Runtime information about a component type
+#[derive(Debug, Clone)]
+pub struct TypeRegistration {
+    name: String,
+    id: ComponentId,
+    type_name: Option<&'static str>,
+    layout: Layout,
+    drop: Option<DropFn>,
+    clone: Option<CloneFn>,
+    debug: Option<DebugFn>,
+    serialize: Option<SerializeFn>,
+}
+
+impl TypeRegistration {
+    /// Describes component `C` under a stable name
+    pub fn of<C: Component>(name: &str) -> Self {
+        let drop = if std::mem::needs_drop::<C>() {
+            Some(drop_ptr::<C> as DropFn)
+        } else {
+            None
+        };
+
+        Self {
+            name: name.to_string(),
+            id: ComponentId::of::<C>(),
+            type_name: Some(type_name::<C>()),
+            layout: Layout::new::<C>(),
+            drop,
+            clone: None,
+            debug: Some(debug_ptr::<C>),
+            serialize: None,
+        }
+    }
+
+    /// Describes a component without rust type by its memory layout and drop function
+    /// Panics if the id belongs to a rust type
+    pub fn dynamic(id: ComponentId, name: &str, layout: Layout, drop: Option<DropFn>) -> Self {
+        assert!(
+            matches!(id, ComponentId::Dynamic(_)),
+            "Component {:?} is not dynamic",
+            id
+        );
+
+        Self {
+            name: name.to_string(),
+            id,
+            type_name: None,
+            layout,
+            drop,
+            clone: None,
+            debug: None,
+            serialize: None,
+        }
+    }
+
+    /// Adds a clone hook, `C` has to be the registered type
+    pub fn with_clone<C: Component + Clone>(mut self) -> Self {
+        self.assert_type::<C>();
+        self.clone = Some(clone_ptr::<C>);
+        self
+    }
+
+    /// Adds a serialize hook, `C` has to be the registered type
+    pub fn with_serialize<C: SerializeComponent>(mut self) -> Self {
+        self.assert_type::<C>();
+        self.serialize = Some(serialize_ptr::<C>);
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn id(&self) -> ComponentId {
+        self.id
+    }
+
+    /// Returns the rust type of the component, dynamic components have none
+    pub fn type_id(&self) -> Option<TypeId> {
+        self.id.type_id()
+    }
+
+    /// Returns the rust type name or the registered name for dynamic components
+    pub fn type_name(&self) -> &str {
+        self.type_name.unwrap_or(&self.name)
+    }
+
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    pub fn drop_fn(&self) -> Option<DropFn> {
+        self.drop
+    }
+
+    pub fn clone_fn(&self) -> Option<CloneFn> {
+        self.clone
+    }
+
+    pub fn debug_fn(&self) -> Option<DebugFn> {
+        self.debug
+    }
+
+    pub fn serialize_fn(&self) -> Option<SerializeFn> {
+        self.serialize
+    }
+
+    // Hooks of `C` may only be added if the registration describes `C` exactly
+    fn assert_type<C: Component>(&self) {
+        assert!(
+            self.id == ComponentId::of::<C>() && self.layout == Layout::new::<C>(),
+            "Registered type is not {}",
+            type_name::<C>()
+        );
+    }
+}
+
+// Keeps track of every registered component type and its name
+#[derive(Debug, Default)]
+pub struct TypeRegistry {
+    // Provides unique ids for dynamic components
+    dynamic_ids: u32,
+    registrations: HashMap<ComponentId, TypeRegistration>,
+    names: HashMap<String, ComponentId>,
+}
+
+impl TypeRegistry {
+    pub fn new() -> Self {
+        Self {
+            dynamic_ids: 0,
+            registrations: HashMap::new(),
+            names: HashMap::new(),
+        }
+    }
+
+    /// Registers a component type, registering the same type again replaces the old registration
+    /// Panics if the name is already used by another type
+    pub fn register(&mut self, registration: TypeRegistration) {
+        let component_id = registration.id();
+
+        if let Some(other) = self.names.get(registration.name()) {
+            assert_eq!(
+                *other,
+                component_id,
+                "Component name {:?} is already registered",
+                registration.name()
+            );
+        }
+
+        if let Some(old) = self.registrations.remove(&component_id) {
+            self.names.remove(old.name());
+        }
+
+        self.names
+            .insert(registration.name().to_string(), component_id);
+        self.registrations.insert(component_id, registration);
+    }
+
+    /// Registers a component that has no rust type and returns its newly created id
+    /// Values of it and `drop` have to be safe to use from any thread, like every other component
+    /// Panics if the name is already used by another type
+    pub fn register_dynamic(
+        &mut self,
+        name: &str,
+        layout: Layout,
+        drop: Option<DropFn>,
+    ) -> ComponentId {
+        let component_id = ComponentId::Dynamic(self.dynamic_ids);
+        self.register(TypeRegistration::dynamic(component_id, name, layout, drop));
+        self.dynamic_ids += 1;
+
+        component_id
+    }
+
+    /// Returns the registration of a component
+    pub fn get(&self, component_id: ComponentId) -> Option<&TypeRegistration> {
+        self.registrations.get(&component_id)
+    }
+
+    /// Returns the registration of component `C`
+    pub fn get_of<C: Component>(&self) -> Option<&TypeRegistration> {
+        self.get(ComponentId::of::<C>())
+    }
+
+    /// Returns the registration with a given name
+    pub fn get_by_name(&self, name: &str) -> Option<&TypeRegistration> {
+        self.names
+            .get(name)
+            .and_then(|component_id| self.get(*component_id))
+    }
+
+    /// Iterates over every registration
+    pub fn iter(&self) -> impl Iterator<Item = &TypeRegistration> {
+        self.registrations.values()
+    }
+
+    /// Returns how much types are registered
+    pub fn len(&self) -> usize {
+        self.registrations.len()
+    }
+
+    /// Returns true if no type is registered
+    pub fn is_empty(&self) -> bool {
+        self.registrations.is_empty()
+    }
+}
+
+/// Type erased reference to a component that is described by its registration
+#[derive(Clone, Copy)]
+pub struct ComponentRef<'a> {
+    ptr: NonNull<u8>,
+    registration: &'a TypeRegistration,
+    marker: PhantomData<&'a ()>,
+}
+
+impl<'a> ComponentRef<'a> {
+    /// # Safety
+    /// `ptr` has to point to a valid value of the registered type that lives for `'a`
+    pub unsafe fn new(ptr: NonNull<u8>, registration: &'a TypeRegistration) -> Self {
+        Self {
+            ptr,
+            registration,
+            marker: PhantomData,
+        }
+    }
+
+    pub fn registration(&self) -> &'a TypeRegistration {
+        self.registration
+    }
+
+    pub fn name(&self) -> &'a str {
+        self.registration.name()
+    }
+
+    pub fn as_ptr(&self) -> NonNull<u8> {
+        self.ptr
+    }
+
+    /// Returns the component if it is of type `C`
+    pub fn downcast_ref<C: Component>(&self) -> Option<&'a C> {
+        if self.registration.id() == ComponentId::of::<C>() {
+            // Safety: The type of the pointer was checked above
+            Some(unsafe { self.ptr.cast::<C>().as_ref() })
+        } else {
+            None
+        }
+    }
+
+    /// Serializes the component if a serialize hook was registered
+    pub fn serialize(&self) -> Option<String> {
+        self.registration
+            .serialize_fn()
+            // Safety: The hook was registered for the type the pointer points to
+            .map(|serialize| unsafe { serialize(self.ptr) })
+    }
+}
+
+impl Debug for ComponentRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.registration.debug_fn() {
+            // Safety: The hook was registered for the type the pointer points to
+            Some(debug) => unsafe { debug(self.ptr, f) },
+            None => write!(f, "{} {{ .. }}", self.registration.name()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{alloc::Layout, any::TypeId};
+
+    use crate::storage::{Component, ComponentId, VecStorage};
+
+    use super::{SerializeComponent, TypeRegistration, TypeRegistry};
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Name(String);
+
+    impl Component for Name {
+        type Storage = VecStorage<Self>;
+    }
+
+    impl SerializeComponent for Name {
+        fn serialize(&self) -> String {
+            self.0.clone()
+        }
+    }
+
+    #[test]
+    fn registry_get_by_name() {
+        let mut registry = TypeRegistry::new();
+        registry.register(TypeRegistration::of::<Name>("name").with_clone::<Name>());
+
+        let registration = registry.get_by_name("name").unwrap();
+
+        assert_eq!(registration.type_id(), Some(TypeId::of::<Name>()));
+        assert!(registration.clone_fn().is_some());
+        assert!(registration.drop_fn().is_some());
+        assert!(registry.get_by_name("health").is_none());
+    }
+
+    #[test]
+    fn registry_reregister_replaces_name() {
+        let mut registry = TypeRegistry::new();
+        registry.register(TypeRegistration::of::<Name>("name"));
+        registry.register(TypeRegistration::of::<Name>("label").with_serialize::<Name>());
+
+        assert_eq!(registry.len(), 1);
+        assert!(registry.get_by_name("name").is_none());
+        assert!(registry.get_of::<Name>().unwrap().serialize_fn().is_some());
+    }
+
+    #[test]
+    #[should_panic(expected = "Component Static")]
+    fn dynamic_registration_of_rust_type_panics() {
+        TypeRegistration::dynamic(ComponentId::of::<Name>(), "name", Layout::new::<u8>(), None);
+    }
+}
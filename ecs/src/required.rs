@@ -0,0 +1,177 @@
+use std::fmt;
+
+use crate::{
+    archetype::EntityLayout,
+    storage::{Component, ComponentId, ComponentIndex, ComponentStorages, Storage},
+};
+
+// Creates the value of a required component and pushes it into its storage
+type Constructor = Box<dyn FnOnce(&mut ComponentStorages) -> ComponentIndex>;
+
+/// Components that are added together with a component if an entity does not have them yet
+/// Components declare them in `Component::required`, requirements of requirements are included
+#[derive(Default)]
+pub struct RequiredComponents {
+    components: Vec<(ComponentId, Constructor)>,
+}
+
+impl RequiredComponents {
+    pub fn new() -> Self {
+        Self {
+            components: Vec::new(),
+        }
+    }
+
+    /// Collects every component that component `C` requires
+    pub fn of<C: Component>() -> Self {
+        let mut required = Self::new();
+        C::required(&mut required);
+
+        required
+    }
+
+    /// Requires component `R`, it is created with its default value
+    pub fn require<R: Component + Default>(&mut self) {
+        self.require_with(R::default);
+    }
+
+    /// Requires component `R`, it is created with `constructor`
+    /// The first requirement of a component wins if it is required several times
+    pub fn require_with<R: Component>(&mut self, constructor: impl FnOnce() -> R + 'static) {
+        let component_id = ComponentId::of::<R>();
+        if self.contains(component_id) {
+            return;
+        }
+
+        self.components.push((
+            component_id,
+            Box::new(|storages| storages.storage_mut::<R>().push_component(constructor())),
+        ));
+        R::required(self);
+    }
+
+    pub fn contains(&self, component_id: ComponentId) -> bool {
+        self.components.iter().any(|(id, _)| *id == component_id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.components.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.components.is_empty()
+    }
+
+    // Creates every required component the layout does not contain and registers them in the layout
+    // Returns the ids of the created components with their index in the storage
+    pub(crate) fn insert_missing(
+        self,
+        layout: &mut EntityLayout,
+        storages: &mut ComponentStorages,
+    ) -> Vec<(ComponentId, ComponentIndex)> {
+        let mut inserted = Vec::new();
+
+        for (component_id, constructor) in self.components {
+            if !layout.contains(component_id) {
+                layout.register_component_id(component_id);
+                inserted.push((component_id, constructor(storages)));
+            }
+        }
+
+        inserted
+    }
+}
+
+impl fmt::Debug for RequiredComponents {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.components.iter().map(|(id, _)| id))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        storage::{Component, VecStorage},
+        world::World,
+    };
+
+    use super::RequiredComponents;
+
+    #[derive(Debug, PartialEq)]
+    struct Sprite(&'static str);
+
+    impl Component for Sprite {
+        type Storage = VecStorage<Self>;
+
+        fn required(components: &mut RequiredComponents) {
+            components.require::<Transform>();
+            components.require_with(|| Visibility(true));
+        }
+    }
+
+    #[derive(Debug, Default, PartialEq)]
+    struct Transform(f32, f32);
+
+    impl Component for Transform {
+        type Storage = VecStorage<Self>;
+
+        fn required(components: &mut RequiredComponents) {
+            components.require::<GlobalTransform>();
+        }
+    }
+
+    #[derive(Debug, Default, PartialEq)]
+    struct GlobalTransform(f32, f32);
+
+    impl Component for GlobalTransform {
+        type Storage = VecStorage<Self>;
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Visibility(bool);
+
+    impl Component for Visibility {
+        type Storage = VecStorage<Self>;
+    }
+
+    #[test]
+    fn spawn_inserts_required_components() {
+        let mut world = World::new();
+        let player = world.spawn(Sprite("player"));
+
+        let entry = world.entry(&player);
+        assert_eq!(
+            entry.get_component::<Transform>(),
+            Some(&Transform(0.0, 0.0))
+        );
+        assert_eq!(
+            entry.get_component::<GlobalTransform>(),
+            Some(&GlobalTransform(0.0, 0.0))
+        );
+        assert_eq!(entry.get_component::<Visibility>(), Some(&Visibility(true)));
+
+        // The entity is placed in its final archetype right away
+        assert_eq!(world.archetypes.len(), 1);
+    }
+
+    #[test]
+    fn existing_components_are_kept() {
+        let mut world = World::new();
+        let tree = world.spawn(Visibility(false));
+        world.entry_mut(&tree).add_component(Transform(4.0, 2.0));
+        world.entry_mut(&tree).add_component(Sprite("tree"));
+
+        let entry = world.entry(&tree);
+        assert_eq!(
+            entry.get_component::<Transform>(),
+            Some(&Transform(4.0, 2.0))
+        );
+        assert_eq!(
+            entry.get_component::<Visibility>(),
+            Some(&Visibility(false))
+        );
+        assert_eq!(entry.archetype().layout().clone().into_iter().count(), 4);
+    }
+}
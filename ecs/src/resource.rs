@@ -0,0 +1,295 @@
+use std::{
+    any::{type_name, Any, TypeId},
+    collections::HashMap,
+    fmt,
+    thread::{self, ThreadId},
+};
+
+use crate::{error::EcsError, non_send::NonSend, world::World};
+
+// Holds the single instances of types that belong to the world instead of an entity
+#[derive(Default)]
+pub struct Resources {
+    resources: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+}
+
+impl Resources {
+    pub fn new() -> Self {
+        Self {
+            resources: HashMap::new(),
+        }
+    }
+
+    /// Inserts a resource and returns the one it replaced
+    pub fn insert<R: Any + Send + Sync>(&mut self, resource: R) -> Option<R> {
+        self.resources
+            .insert(TypeId::of::<R>(), Box::new(resource))
+            .map(|resource| *resource.downcast::<R>().unwrap())
+    }
+
+    pub fn remove<R: Any + Send + Sync>(&mut self) -> Option<R> {
+        self.resources
+            .remove(&TypeId::of::<R>())
+            .map(|resource| *resource.downcast::<R>().unwrap())
+    }
+
+    pub fn get<R: Any + Send + Sync>(&self) -> Option<&R> {
+        self.resources
+            .get(&TypeId::of::<R>())
+            .map(|resource| resource.downcast_ref::<R>().unwrap())
+    }
+
+    pub fn get_mut<R: Any + Send + Sync>(&mut self) -> Option<&mut R> {
+        self.resources
+            .get_mut(&TypeId::of::<R>())
+            .map(|resource| resource.downcast_mut::<R>().unwrap())
+    }
+
+    pub fn contains<R: Any + Send + Sync>(&self) -> bool {
+        self.resources.contains_key(&TypeId::of::<R>())
+    }
+
+    pub fn len(&self) -> usize {
+        self.resources.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.resources.is_empty()
+    }
+}
+
+impl fmt::Debug for Resources {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Resources")
+            .field("resources", &self.resources.len())
+            .finish()
+    }
+}
+
+// Holds resources that are not `Send`, they all belong to the thread that inserted the first of them
+// Dropping the resources on another thread leaks them
+#[derive(Default)]
+pub struct NonSendResources {
+    resources: HashMap<TypeId, NonSend<Box<dyn Any>>>,
+    thread: Option<ThreadId>,
+}
+
+impl NonSendResources {
+    pub fn new() -> Self {
+        Self {
+            resources: HashMap::new(),
+            thread: None,
+        }
+    }
+
+    /// Returns the thread the resources belong to, none if there are no resources
+    pub fn thread(&self) -> Option<ThreadId> {
+        self.thread
+    }
+
+    /// Inserts a resource and returns the one it replaced
+    /// Fails if the resources belong to another thread
+    pub fn try_insert<R: Any>(&mut self, resource: R) -> Result<Option<R>, EcsError> {
+        let current = thread::current().id();
+        if self.thread.is_some_and(|thread| thread != current) {
+            return Err(EcsError::WrongThread {
+                value: type_name::<R>(),
+            });
+        }
+
+        let replaced = self.try_remove::<R>()?;
+        let resource: Box<dyn Any> = Box::new(resource);
+        self.resources
+            .insert(TypeId::of::<R>(), NonSend::new(resource));
+        self.thread = Some(current);
+
+        Ok(replaced)
+    }
+
+    /// Fails if the resource was inserted on another thread, it stays in place then
+    pub fn try_remove<R: Any>(&mut self) -> Result<Option<R>, EcsError> {
+        self.check_thread::<R>()?;
+
+        let removed = self
+            .resources
+            .remove(&TypeId::of::<R>())
+            .map(|resource| *resource.into_inner().downcast::<R>().unwrap());
+        if self.resources.is_empty() {
+            self.thread = None;
+        }
+
+        Ok(removed)
+    }
+
+    /// Fails if the resource was inserted on another thread
+    pub fn try_get<R: Any>(&self) -> Result<Option<&R>, EcsError> {
+        self.check_thread::<R>()?;
+
+        Ok(self
+            .resources
+            .get(&TypeId::of::<R>())
+            .map(|resource| resource.get().downcast_ref::<R>().unwrap()))
+    }
+
+    /// Fails if the resource was inserted on another thread
+    pub fn try_get_mut<R: Any>(&mut self) -> Result<Option<&mut R>, EcsError> {
+        self.check_thread::<R>()?;
+
+        Ok(self
+            .resources
+            .get_mut(&TypeId::of::<R>())
+            .map(|resource| resource.get_mut().downcast_mut::<R>().unwrap()))
+    }
+
+    /// Checks if the resource exists, no matter which thread inserted it
+    pub fn contains<R: Any>(&self) -> bool {
+        self.resources.contains_key(&TypeId::of::<R>())
+    }
+
+    pub fn len(&self) -> usize {
+        self.resources.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.resources.is_empty()
+    }
+
+    fn check_thread<R: Any>(&self) -> Result<(), EcsError> {
+        match self.resources.get(&TypeId::of::<R>()) {
+            Some(resource) if !resource.is_accessible() => Err(EcsError::WrongThread {
+                value: type_name::<R>(),
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl fmt::Debug for NonSendResources {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NonSendResources")
+            .field("resources", &self.resources.len())
+            .field("thread", &self.thread)
+            .finish()
+    }
+}
+
+impl World {
+    /// Inserts a resource and returns the one of the same type it replaced
+    pub fn insert_resource<R: Any + Send + Sync>(&mut self, resource: R) -> Option<R> {
+        self.resources.insert(resource)
+    }
+
+    pub fn remove_resource<R: Any + Send + Sync>(&mut self) -> Option<R> {
+        self.resources.remove::<R>()
+    }
+
+    pub fn resource<R: Any + Send + Sync>(&self) -> Option<&R> {
+        self.resources.get::<R>()
+    }
+
+    pub fn resource_mut<R: Any + Send + Sync>(&mut self) -> Option<&mut R> {
+        self.resources.get_mut::<R>()
+    }
+
+    pub fn contains_resource<R: Any + Send + Sync>(&self) -> bool {
+        self.resources.contains::<R>()
+    }
+
+    /// Inserts a resource that is not `Send`, it can only be used on the current thread
+    /// The thread that inserts the first non-send resource is the main thread of the world
+    /// If the world is dropped on another thread the resource is leaked instead of dropped
+    /// Returns the resource of the same type it replaced
+    /// Panics if the non-send resources of the world belong to another thread
+    pub fn insert_non_send_resource<R: Any>(&mut self, resource: R) -> Option<R> {
+        self.non_send_resources
+            .try_insert(resource)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Panics if the resource was inserted on another thread
+    pub fn remove_non_send_resource<R: Any>(&mut self) -> Option<R> {
+        self.non_send_resources
+            .try_remove::<R>()
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Panics if the resource was inserted on another thread
+    pub fn non_send_resource<R: Any>(&self) -> Option<&R> {
+        self.try_non_send_resource::<R>()
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Panics if the resource was inserted on another thread
+    pub fn non_send_resource_mut<R: Any>(&mut self) -> Option<&mut R> {
+        self.try_non_send_resource_mut::<R>()
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_non_send_resource<R: Any>(&self) -> Result<Option<&R>, EcsError> {
+        self.non_send_resources.try_get::<R>()
+    }
+
+    pub fn try_non_send_resource_mut<R: Any>(&mut self) -> Result<Option<&mut R>, EcsError> {
+        self.non_send_resources.try_get_mut::<R>()
+    }
+
+    pub fn contains_non_send_resource<R: Any>(&self) -> bool {
+        self.non_send_resources.contains::<R>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{any::type_name, rc::Rc, thread};
+
+    use crate::{error::EcsError, world::World};
+
+    #[derive(Debug, PartialEq)]
+    struct Gravity(f32);
+
+    #[test]
+    fn insert_and_replace_resource() {
+        let mut world = World::new();
+
+        assert_eq!(world.insert_resource(Gravity(9.81)), None);
+        world.resource_mut::<Gravity>().unwrap().0 = 1.62;
+
+        assert_eq!(world.insert_resource(Gravity(3.71)), Some(Gravity(1.62)));
+        assert_eq!(world.resource::<Gravity>(), Some(&Gravity(3.71)));
+        assert_eq!(world.remove_resource::<Gravity>(), Some(Gravity(3.71)));
+        assert!(!world.contains_resource::<Gravity>());
+    }
+
+    #[test]
+    fn non_send_resources_stay_on_their_thread() {
+        let mut world = World::new();
+        world.insert_non_send_resource(Rc::new(Gravity(9.81)));
+        world.non_send_resource_mut::<Rc<Gravity>>().unwrap();
+
+        let mut world = thread::spawn(move || {
+            assert!(world.contains_non_send_resource::<Rc<Gravity>>());
+            assert_eq!(
+                world.try_non_send_resource::<Rc<Gravity>>().err(),
+                Some(EcsError::WrongThread {
+                    value: type_name::<Rc<Gravity>>(),
+                })
+            );
+            assert_eq!(
+                world.non_send_resources.try_insert(Rc::new(1.62)).err(),
+                Some(EcsError::WrongThread {
+                    value: type_name::<Rc<f64>>(),
+                })
+            );
+            world
+        })
+        .join()
+        .unwrap();
+
+        assert_eq!(
+            world.remove_non_send_resource::<Rc<Gravity>>(),
+            Some(Rc::new(Gravity(9.81)))
+        );
+        assert!(world.non_send_resources.is_empty());
+        assert_eq!(world.non_send_resources.thread(), None);
+    }
+}
@@ -0,0 +1,734 @@
+use std::{
+    collections::BTreeSet,
+    fmt,
+    thread::{self, ThreadId},
+    time::Duration,
+};
+
+use crate::{
+    condition::{IntoRunCondition, RunCondition},
+    error::EcsError,
+    non_send::NonSend,
+    time::Time,
+    world::World,
+};
+
+/// Function that runs once every time its stage runs and gets full access to the world
+/// Systems are `Send` so a schedule can run its world on another thread
+pub type System = Box<dyn FnMut(&mut World) + Send>;
+
+/// System that does not have to be `Send`, it is bound to the thread that created it
+pub type LocalSystem = NonSend<Box<dyn FnMut(&mut World)>>;
+
+// A system of a config, main thread systems may be local
+enum SystemFn {
+    Shared(System),
+    Local(LocalSystem),
+}
+
+pub const PRE_UPDATE: &str = "PreUpdate";
+pub const UPDATE: &str = "Update";
+pub const POST_UPDATE: &str = "PostUpdate";
+pub const FIXED_UPDATE: &str = "FixedUpdate";
+
+/// System together with the labels, ordering constraints and run conditions it is added with
+pub struct SystemConfig {
+    system: SystemFn,
+    labels: Vec<String>,
+    before: Vec<String>,
+    after: Vec<String>,
+    conditions: Vec<RunCondition>,
+    main_thread: bool,
+}
+
+impl SystemConfig {
+    pub fn new(system: impl FnMut(&mut World) + Send + 'static) -> Self {
+        Self {
+            system: SystemFn::Shared(Box::new(system)),
+            labels: Vec::new(),
+            before: Vec::new(),
+            after: Vec::new(),
+            conditions: Vec::new(),
+            main_thread: false,
+        }
+    }
+
+    /// Creates a config for a system that is not `Send`, it only runs on the main thread
+    /// The system is bound to the current thread, so the schedule has to run there
+    pub fn local(system: impl FnMut(&mut World) + 'static) -> Self {
+        let system: Box<dyn FnMut(&mut World)> = Box::new(system);
+
+        Self {
+            system: SystemFn::Local(NonSend::new(system)),
+            labels: Vec::new(),
+            before: Vec::new(),
+            after: Vec::new(),
+            conditions: Vec::new(),
+            main_thread: true,
+        }
+    }
+
+    pub fn labels(&self) -> &[String] {
+        &self.labels
+    }
+
+    pub fn is_main_thread(&self) -> bool {
+        self.main_thread
+    }
+
+    // Name used in errors, the first label or the position in the stage
+    fn name(&self, index: usize) -> String {
+        match self.labels.first() {
+            Some(label) => label.clone(),
+            None => format!("system {}", index),
+        }
+    }
+}
+
+/// Turns systems into configs so labels and ordering constraints can be chained onto them
+pub trait IntoSystemConfig {
+    fn into_config(self) -> SystemConfig;
+
+    /// Adds a label other systems of the same stage can be ordered by, several systems can share one
+    fn label(self, label: &str) -> SystemConfig
+    where
+        Self: Sized,
+    {
+        let mut config = self.into_config();
+        config.labels.push(label.to_string());
+        config
+    }
+
+    /// Runs the system before every system with the label
+    fn before(self, label: &str) -> SystemConfig
+    where
+        Self: Sized,
+    {
+        let mut config = self.into_config();
+        config.before.push(label.to_string());
+        config
+    }
+
+    /// Runs the system after every system with the label
+    fn after(self, label: &str) -> SystemConfig
+    where
+        Self: Sized,
+    {
+        let mut config = self.into_config();
+        config.after.push(label.to_string());
+        config
+    }
+
+    /// Only runs the system while the condition is true, several conditions all have to be true
+    fn run_if(self, condition: impl IntoRunCondition) -> SystemConfig
+    where
+        Self: Sized,
+    {
+        let mut config = self.into_config();
+        config.conditions.push(condition.into_condition());
+        config
+    }
+
+    /// Only allows the system to run on the main thread, needed for systems that access non-send resources
+    /// The main thread is the thread that owns the non-send resources of the world the schedule runs on,
+    /// or the thread that created the schedule if the world has no non-send resources
+    /// Systems that are not `Send` are added through `SystemConfig::local` instead
+    fn on_main_thread(self) -> SystemConfig
+    where
+        Self: Sized,
+    {
+        let mut config = self.into_config();
+        config.main_thread = true;
+        config
+    }
+}
+
+impl IntoSystemConfig for SystemConfig {
+    fn into_config(self) -> SystemConfig {
+        self
+    }
+}
+
+impl<F: FnMut(&mut World) + Send + 'static> IntoSystemConfig for F {
+    fn into_config(self) -> SystemConfig {
+        SystemConfig::new(self)
+    }
+}
+
+/// Makes a stage run at a fixed rate independent of the frame rate
+/// The frame time of the `Time` resource is accumulated and the stage runs once for every full step
+#[derive(Debug, Clone)]
+pub struct FixedTimestep {
+    step: Duration,
+    max_steps: u32,
+    accumulator: Duration,
+}
+
+impl FixedTimestep {
+    /// Panics if the step is zero
+    pub fn new(step: Duration) -> Self {
+        assert!(!step.is_zero(), "Fixed timestep can't be zero!");
+
+        Self {
+            step,
+            max_steps: 5,
+            accumulator: Duration::ZERO,
+        }
+    }
+
+    pub fn from_hz(hz: f64) -> Self {
+        Self::new(Duration::from_secs_f64(1.0 / hz))
+    }
+
+    /// Limits how often the stage runs in one frame to catch up after a long frame
+    /// Time that can't be caught up is dropped so slow frames don't pile up
+    pub fn with_max_steps(mut self, max_steps: u32) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    pub fn step(&self) -> Duration {
+        self.step
+    }
+
+    /// Time that passed but was not enough for another step yet
+    pub fn accumulator(&self) -> Duration {
+        self.accumulator
+    }
+
+    // Accumulates the frame time and returns how many steps have to run this frame
+    fn steps(&mut self, delta: Duration) -> u32 {
+        self.accumulator += delta;
+
+        let mut steps = 0;
+        while self.accumulator >= self.step && steps < self.max_steps {
+            self.accumulator -= self.step;
+            steps += 1;
+        }
+
+        if self.accumulator >= self.step {
+            let remainder = self.accumulator.as_nanos() % self.step.as_nanos();
+            self.accumulator = Duration::from_nanos(remainder as u64);
+        }
+
+        steps
+    }
+}
+
+// Named group of systems that run one after another
+struct Stage {
+    name: String,
+    systems: Vec<SystemConfig>,
+    timestep: Option<FixedTimestep>,
+}
+
+impl Stage {
+    // Orders the systems so every constraint holds, unconstrained systems keep the order they were added in
+    fn sort(&mut self) -> Result<(), EcsError> {
+        let count = self.systems.len();
+
+        // Every system has to run before the systems it has an edge to
+        let mut edges = vec![Vec::new(); count];
+        let mut in_degree = vec![0; count];
+        for (index, config) in self.systems.iter().enumerate() {
+            for label in &config.before {
+                for other in self.labeled(label)? {
+                    edges[index].push(other);
+                    in_degree[other] += 1;
+                }
+            }
+
+            for label in &config.after {
+                for other in self.labeled(label)? {
+                    edges[other].push(index);
+                    in_degree[index] += 1;
+                }
+            }
+        }
+
+        let mut ready: BTreeSet<usize> = (0..count).filter(|i| in_degree[*i] == 0).collect();
+        let mut order = Vec::with_capacity(count);
+        while let Some(index) = ready.pop_first() {
+            order.push(index);
+
+            for other in &edges[index] {
+                in_degree[*other] -= 1;
+                if in_degree[*other] == 0 {
+                    ready.insert(*other);
+                }
+            }
+        }
+
+        if order.len() < count {
+            return Err(EcsError::SystemOrderCycle {
+                stage: self.name.clone(),
+                systems: self.find_cycle(&edges, &in_degree),
+            });
+        }
+
+        let mut systems: Vec<Option<SystemConfig>> = self.systems.drain(..).map(Some).collect();
+        self.systems = order
+            .into_iter()
+            .map(|index| systems[index].take().unwrap())
+            .collect();
+
+        Ok(())
+    }
+
+    // Returns the indices of every system with the label
+    fn labeled(&self, label: &str) -> Result<Vec<usize>, EcsError> {
+        let indices: Vec<usize> = self
+            .systems
+            .iter()
+            .enumerate()
+            .filter(|(_, config)| config.labels.iter().any(|l| l == label))
+            .map(|(index, _)| index)
+            .collect();
+
+        if indices.is_empty() {
+            return Err(EcsError::UnknownSystemLabel {
+                stage: self.name.clone(),
+                label: label.to_string(),
+            });
+        }
+
+        Ok(indices)
+    }
+
+    // Returns the name of the first system that can't run on the current thread
+    fn main_thread_system(&self, on_main_thread: bool) -> Option<String> {
+        self.systems
+            .iter()
+            .enumerate()
+            .find(|(_, config)| match &config.system {
+                SystemFn::Shared(_) => config.main_thread && !on_main_thread,
+                SystemFn::Local(system) => !on_main_thread || !system.is_accessible(),
+            })
+            .map(|(index, config)| config.name(index))
+    }
+
+    // Walks backwards from a system that could not be sorted until a system repeats
+    // Every unsorted system has an unsorted predecessor so the walk always ends in a cycle
+    fn find_cycle(&self, edges: &[Vec<usize>], in_degree: &[usize]) -> Vec<String> {
+        let predecessor = |index: usize| {
+            (0..edges.len())
+                .find(|other| in_degree[*other] > 0 && edges[*other].contains(&index))
+                .unwrap()
+        };
+
+        let mut path = vec![(0..edges.len()).find(|i| in_degree[*i] > 0).unwrap()];
+        loop {
+            let next = predecessor(*path.last().unwrap());
+
+            if let Some(start) = path.iter().position(|index| *index == next) {
+                let mut cycle: Vec<usize> = path[start..].iter().rev().copied().collect();
+
+                // Start with the system added first so the error does not depend on the walk
+                let first = (0..cycle.len()).min_by_key(|i| cycle[*i]).unwrap();
+                cycle.rotate_left(first);
+                cycle.push(cycle[0]);
+
+                return cycle
+                    .into_iter()
+                    .map(|index| self.systems[index].name(index))
+                    .collect();
+            }
+
+            path.push(next);
+        }
+    }
+
+    fn run(&mut self, world: &mut World) {
+        let runs = match &mut self.timestep {
+            Some(timestep) => {
+                let delta = world
+                    .resource::<Time>()
+                    .map(Time::delta)
+                    .unwrap_or_default();
+                timestep.steps(delta)
+            }
+            None => 1,
+        };
+
+        for _ in 0..runs {
+            for config in &mut self.systems {
+                let run = config
+                    .conditions
+                    .iter_mut()
+                    .all(|condition| condition.evaluate(world));
+
+                if run {
+                    match &mut config.system {
+                        SystemFn::Shared(system) => system(world),
+                        SystemFn::Local(system) => system.get_mut()(world),
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Runs systems in named stages, the stages run in the order they were added
+/// Systems of a stage run in the order they were added unless they are ordered by labels
+/// Systems added with `on_main_thread` only run on the thread that owns the non-send resources of the world
+pub struct Schedule {
+    stages: Vec<Stage>,
+    built: bool,
+    main_thread: ThreadId,
+}
+
+impl Schedule {
+    pub fn new() -> Self {
+        Self {
+            stages: Vec::new(),
+            built: false,
+            main_thread: thread::current().id(),
+        }
+    }
+
+    /// Returns the thread that created the schedule, it is the main thread for worlds without non-send resources
+    pub fn main_thread(&self) -> ThreadId {
+        self.main_thread
+    }
+
+    /// Adds a stage that runs once per frame after the stages added before
+    /// Panics if a stage with the same name exists
+    pub fn add_stage(&mut self, name: &str) -> &mut Self {
+        self.insert_stage(name, None)
+    }
+
+    /// Adds a stage that runs zero or more times per frame to keep up with a fixed rate
+    /// Panics if a stage with the same name exists
+    pub fn add_fixed_stage(&mut self, name: &str, timestep: FixedTimestep) -> &mut Self {
+        self.insert_stage(name, Some(timestep))
+    }
+
+    /// Adds a system to the end of a stage, it can be a plain function or a config with labels
+    /// Panics if the stage does not exist
+    pub fn add_system(&mut self, stage: &str, system: impl IntoSystemConfig) -> &mut Self {
+        self.stage_mut(stage).systems.push(system.into_config());
+        self.built = false;
+        self
+    }
+
+    /// Sorts the systems of every stage by their ordering constraints
+    /// Fails if constraints form a cycle or refer to a label that no system of the stage has
+    pub fn build(&mut self) -> Result<(), EcsError> {
+        for stage in &mut self.stages {
+            stage.sort()?;
+        }
+
+        self.built = true;
+        Ok(())
+    }
+
+    /// Returns the fixed timestep of a stage, none if the stage runs once per frame
+    pub fn timestep(&self, stage: &str) -> Option<&FixedTimestep> {
+        self.stages
+            .iter()
+            .find(|s| s.name == stage)
+            .and_then(|s| s.timestep.as_ref())
+    }
+
+    /// Runs every stage once, fixed stages run as often as the `Time` resource requires
+    /// The schedule is built first if systems were added since the last build
+    /// Panics if the schedule can't be built or has to run on its main thread
+    pub fn run(&mut self, world: &mut World) {
+        self.try_run(world)
+            .unwrap_or_else(|error| panic!("{}", error));
+    }
+
+    /// Runs every stage once, fixed stages run as often as the `Time` resource requires
+    /// Fails without running any system if the schedule can't be built or if it has systems that
+    /// have to run on the main thread and it is run on another thread
+    pub fn try_run(&mut self, world: &mut World) -> Result<(), EcsError> {
+        if !self.built {
+            self.build()?;
+        }
+
+        let main_thread = world
+            .non_send_resources
+            .thread()
+            .unwrap_or(self.main_thread);
+        let on_main_thread = thread::current().id() == main_thread;
+        for stage in &self.stages {
+            if let Some(system) = stage.main_thread_system(on_main_thread) {
+                return Err(EcsError::MainThreadSystem {
+                    stage: stage.name.clone(),
+                    system,
+                });
+            }
+        }
+
+        for stage in &mut self.stages {
+            stage.run(world);
+        }
+
+        Ok(())
+    }
+
+    fn insert_stage(&mut self, name: &str, timestep: Option<FixedTimestep>) -> &mut Self {
+        assert!(
+            self.stages.iter().all(|stage| stage.name != name),
+            "Stage {} exists already!",
+            name
+        );
+
+        self.stages.push(Stage {
+            name: name.to_string(),
+            systems: Vec::new(),
+            timestep,
+        });
+        self
+    }
+
+    fn stage_mut(&mut self, name: &str) -> &mut Stage {
+        self.stages
+            .iter_mut()
+            .find(|stage| stage.name == name)
+            .unwrap_or_else(|| panic!("Stage {} does not exist!", name))
+    }
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut stages = f.debug_map();
+        for stage in &self.stages {
+            stages.entry(&stage.name, &stage.systems.len());
+        }
+        stages.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{rc::Rc, thread, time::Duration};
+
+    use crate::{error::EcsError, time::Time, world::World};
+
+    use super::{
+        FixedTimestep, IntoSystemConfig, Schedule, SystemConfig, FIXED_UPDATE, POST_UPDATE,
+        PRE_UPDATE, UPDATE,
+    };
+
+    #[derive(Debug, Default)]
+    struct Log(Vec<&'static str>);
+
+    fn log(entry: &'static str) -> impl FnMut(&mut World) + Send {
+        move |world| world.resource_mut::<Log>().unwrap().0.push(entry)
+    }
+
+    #[test]
+    fn stages_run_in_order() {
+        let mut world = World::new();
+        world.insert_resource(Log::default());
+
+        let mut schedule = Schedule::new();
+        schedule
+            .add_stage(PRE_UPDATE)
+            .add_stage(UPDATE)
+            .add_stage(POST_UPDATE)
+            .add_system(POST_UPDATE, log("render"))
+            .add_system(UPDATE, log("move"))
+            .add_system(PRE_UPDATE, log("input"))
+            .add_system(UPDATE, log("collide"));
+
+        schedule.run(&mut world);
+
+        assert_eq!(
+            world.resource::<Log>().unwrap().0,
+            vec!["input", "move", "collide", "render"]
+        );
+    }
+
+    #[test]
+    fn fixed_stage_catches_up() {
+        let mut world = World::new();
+        world.insert_resource(Log::default());
+        world.insert_resource(Time::new());
+
+        let mut schedule = Schedule::new();
+        schedule
+            .add_fixed_stage(
+                FIXED_UPDATE,
+                FixedTimestep::new(Duration::from_millis(10)).with_max_steps(3),
+            )
+            .add_stage(UPDATE)
+            .add_system(FIXED_UPDATE, log("physics"))
+            .add_system(UPDATE, log("frame"));
+
+        let mut frame = |world: &mut World, millis| {
+            world.resource_mut::<Log>().unwrap().0.clear();
+            world
+                .resource_mut::<Time>()
+                .unwrap()
+                .advance(Duration::from_millis(millis));
+            schedule.run(world);
+
+            world.resource::<Log>().unwrap().0.clone()
+        };
+
+        assert_eq!(frame(&mut world, 25), vec!["physics", "physics", "frame"]);
+        assert_eq!(frame(&mut world, 4), vec!["frame"]);
+        assert_eq!(frame(&mut world, 1), vec!["physics", "frame"]);
+        assert_eq!(
+            frame(&mut world, 100),
+            vec!["physics", "physics", "physics", "frame"]
+        );
+        assert_eq!(
+            schedule.timestep(FIXED_UPDATE).unwrap().accumulator(),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn systems_follow_ordering_constraints() {
+        let mut world = World::new();
+        world.insert_resource(Log::default());
+
+        let mut schedule = Schedule::new();
+        schedule
+            .add_stage(UPDATE)
+            .add_system(UPDATE, log("render").label("render").after("physics"))
+            .add_system(UPDATE, log("audio"))
+            .add_system(UPDATE, log("collide").label("physics").after("move"))
+            .add_system(UPDATE, log("move").label("move").label("physics"))
+            .add_system(UPDATE, log("input").before("move"));
+
+        schedule.run(&mut world);
+
+        assert_eq!(
+            world.resource::<Log>().unwrap().0,
+            vec!["audio", "input", "move", "collide", "render"]
+        );
+    }
+
+    #[test]
+    fn ordering_errors() {
+        let mut schedule = Schedule::new();
+        schedule
+            .add_stage(UPDATE)
+            .add_system(UPDATE, log("a").label("a").after("c"))
+            .add_system(UPDATE, log("b").label("b").after("a"))
+            .add_system(UPDATE, log("c").label("c").after("b"))
+            .add_system(UPDATE, log("d").after("a"));
+
+        assert_eq!(
+            schedule.build(),
+            Err(EcsError::SystemOrderCycle {
+                stage: UPDATE.to_string(),
+                systems: vec!["a", "b", "c", "a"]
+                    .into_iter()
+                    .map(String::from)
+                    .collect(),
+            })
+        );
+
+        let mut schedule = Schedule::new();
+        schedule
+            .add_stage(UPDATE)
+            .add_system(UPDATE, log("a").before("physics"));
+
+        assert_eq!(
+            schedule.build(),
+            Err(EcsError::UnknownSystemLabel {
+                stage: UPDATE.to_string(),
+                label: "physics".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn main_thread_systems_refuse_other_threads() {
+        let mut world = World::new();
+        world.insert_resource(Log::default());
+        world.insert_non_send_resource(Rc::new("window"));
+
+        let mut schedule = Schedule::new();
+        schedule
+            .add_stage(UPDATE)
+            .add_system(UPDATE, log("physics"))
+            .add_system(
+                UPDATE,
+                (|world: &mut World| {
+                    let window = *world.non_send_resource::<Rc<&str>>().unwrap().clone();
+                    world.resource_mut::<Log>().unwrap().0.push(window);
+                })
+                .label("render")
+                .on_main_thread(),
+            );
+        schedule.run(&mut world);
+
+        let (mut schedule, mut world) = thread::spawn(move || {
+            assert_eq!(
+                schedule.try_run(&mut world),
+                Err(EcsError::MainThreadSystem {
+                    stage: UPDATE.to_string(),
+                    system: "render".to_string(),
+                })
+            );
+            (schedule, world)
+        })
+        .join()
+        .unwrap();
+
+        schedule.run(&mut world);
+        assert_eq!(
+            world.resource::<Log>().unwrap().0,
+            vec!["physics", "window", "physics", "window"]
+        );
+
+        // The world decides the main thread, not the thread that created the schedule
+        let world = thread::spawn(move || {
+            let mut schedule = Schedule::new();
+            schedule
+                .add_stage(UPDATE)
+                .add_system(UPDATE, log("render").label("render").on_main_thread());
+            assert_eq!(
+                schedule.try_run(&mut world),
+                Err(EcsError::MainThreadSystem {
+                    stage: UPDATE.to_string(),
+                    system: "render".to_string(),
+                })
+            );
+            world
+        })
+        .join()
+        .unwrap();
+        assert_eq!(world.resource::<Log>().unwrap().0.len(), 4);
+    }
+
+    #[test]
+    fn local_systems_do_not_have_to_be_send() {
+        let mut world = World::new();
+        world.insert_resource(Log::default());
+
+        let window = Rc::new("window");
+        let mut schedule = Schedule::new();
+        schedule.add_stage(UPDATE).add_system(
+            UPDATE,
+            SystemConfig::local(move |world| world.resource_mut::<Log>().unwrap().0.push(*window))
+                .label("render"),
+        );
+        schedule.run(&mut world);
+        assert_eq!(world.resource::<Log>().unwrap().0, vec!["window"]);
+
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                assert_eq!(
+                    schedule.try_run(&mut world),
+                    Err(EcsError::MainThreadSystem {
+                        stage: UPDATE.to_string(),
+                        system: "render".to_string(),
+                    })
+                );
+            });
+        });
+    }
+}
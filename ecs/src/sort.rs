@@ -0,0 +1,208 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use crate::{
+    archetype::{ArchetypeIndex, Entity},
+    entry::EntryRef,
+    query::Query,
+    storage::{Component, ComponentId},
+    world::World,
+};
+
+// New entity order of every matching archetype and the sorted entities of all of them
+type SortedRows = (Vec<(ArchetypeIndex, Vec<u32>)>, Vec<Entity>);
+
+impl World {
+    /// Returns every entity that matches the query ordered by its component `C`
+    /// Entities without `C` are left out, entities with equal components keep their previous order
+    /// The entities of every archetype are kept in that order, so sorting again is cheap if few components changed
+    pub fn query_sorted<C: Component + Ord>(&mut self, query: &Query) -> Vec<Entity> {
+        let mut components = query.components().clone();
+        components.push(ComponentId::of::<C>());
+
+        let (rows, sorted) = self.sort_rows(&components, |world, entity| {
+            world.get_component::<C>(entity).unwrap()
+        });
+        self.reorder_rows(rows);
+
+        sorted
+    }
+
+    /// Returns every entity that matches the query ordered by the key the function returns for it
+    /// Entities with equal keys keep their previous order
+    /// The entities of every archetype are kept in that order, so sorting again is cheap if few keys changed
+    pub fn query_sorted_by_key<K: Ord>(
+        &mut self,
+        query: &Query,
+        mut key: impl FnMut(&EntryRef) -> K,
+    ) -> Vec<Entity> {
+        let (rows, sorted) =
+            self.sort_rows(
+                query.components(),
+                |world, entity| key(&world.entry(entity)),
+            );
+        self.reorder_rows(rows);
+
+        sorted
+    }
+
+    fn sort_rows<'w, K: Ord>(
+        &'w self,
+        components: &[ComponentId],
+        mut key: impl FnMut(&'w World, &Entity) -> K,
+    ) -> SortedRows {
+        let mut rows = Vec::new();
+        let mut runs = Vec::new();
+
+        for index in 0..self.archetypes.len() as ArchetypeIndex {
+            let archetype = self.archetypes.get(index);
+            let matches = components
+                .iter()
+                .all(|component_id| archetype.layout().contains(*component_id));
+            if !matches {
+                continue;
+            }
+
+            let mut archetype_keyed: Vec<(K, Entity)> = archetype
+                .entity_ids()
+                .iter()
+                .map(|id| (key(self, &Entity(*id)), Entity(*id)))
+                .collect();
+
+            // The stable sort detects runs that are sorted already, rows sorted before only cost a pass
+            archetype_keyed.sort_by(|a, b| a.0.cmp(&b.0));
+            rows.push((
+                index,
+                archetype_keyed
+                    .iter()
+                    .map(|(_, entity)| entity.id())
+                    .collect(),
+            ));
+            runs.push(archetype_keyed.into_iter());
+        }
+
+        // Merges the sorted runs of the archetypes, equal keys are taken from the earlier archetype first
+        let mut sorted = Vec::new();
+        let mut heads = BinaryHeap::new();
+        for (run, entities) in runs.iter_mut().enumerate() {
+            if let Some((key, entity)) = entities.next() {
+                heads.push(Reverse((key, run, entity.id())));
+            }
+        }
+        while let Some(Reverse((_, run, id))) = heads.pop() {
+            sorted.push(Entity(id));
+            if let Some((key, entity)) = runs[run].next() {
+                heads.push(Reverse((key, run, entity.id())));
+            }
+        }
+
+        (rows, sorted)
+    }
+
+    fn reorder_rows(&mut self, rows: Vec<(ArchetypeIndex, Vec<u32>)>) {
+        for (index, entity_ids) in rows {
+            self.archetypes.get_mut(index).reorder_entities(entity_ids);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Reverse;
+
+    use crate::{
+        archetype::Entity,
+        query::Query,
+        storage::{Component, VecStorage},
+        world::World,
+    };
+
+    #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+    struct Layer(u8);
+
+    impl Component for Layer {
+        type Storage = VecStorage<Self>;
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Sprite(&'static str);
+
+    impl Component for Sprite {
+        type Storage = VecStorage<Self>;
+    }
+
+    #[test]
+    fn query_sorted_by_component() {
+        let mut world = World::new();
+        let background = world.spawn(Layer(0));
+        let player = world.spawn(Layer(2));
+        let tree = world.spawn(Layer(1));
+        let cursor = world.spawn(Sprite("cursor"));
+        for (entity, name) in [(background, "background"), (player, "player")] {
+            world.entry_mut(&entity).add_component(Sprite(name));
+        }
+
+        let query = Query::builder().with::<Sprite>().build();
+        assert_eq!(
+            world.query_sorted::<Layer>(&query),
+            vec![background, player]
+        );
+
+        let all = Query::builder().build();
+        assert_eq!(
+            world.query_sorted::<Layer>(&all),
+            vec![background, tree, player]
+        );
+
+        world.get_component_mut::<Layer>(&background).unwrap().0 = 3;
+        assert_eq!(
+            world.query_sorted::<Layer>(&all),
+            vec![tree, player, background]
+        );
+
+        // Rows stay sorted so chunks come out in layer order
+        let archetype = world.entry(&player).archetype().index();
+        assert_eq!(
+            world.archetypes.get(archetype).entity_ids(),
+            &[player.id(), background.id()]
+        );
+        let layers: Vec<u8> = world
+            .query_chunks::<(&Layer, &Sprite)>(8)
+            .flat_map(|(layers, _)| layers.iter().map(|layer| layer.0))
+            .collect();
+        assert_eq!(layers, vec![2, 3]);
+        assert!(!world.query_sorted::<Layer>(&all).contains(&cursor));
+    }
+
+    #[test]
+    fn query_sorted_by_key_function() {
+        let mut world = World::new();
+        let entities: Vec<Entity> = [4, 1, 3]
+            .into_iter()
+            .map(|layer| world.spawn(Layer(layer)))
+            .collect();
+
+        let query = Query::builder().with::<Layer>().build();
+        let sorted = world.query_sorted_by_key(&query, |entry| {
+            Reverse(entry.get_component::<Layer>().unwrap().0)
+        });
+
+        assert_eq!(sorted, vec![entities[0], entities[2], entities[1]]);
+    }
+
+    #[test]
+    fn equal_keys_keep_archetype_order() {
+        let mut world = World::new();
+        let first = world.spawn(Layer(1));
+        let second = world.spawn(Layer(1));
+        let third = world.spawn(Layer(0));
+        world.entry_mut(&third).add_component(Sprite("third"));
+        world.entry_mut(&second).add_component(Sprite("second"));
+        let fourth = world.spawn(Layer(1));
+
+        let all = Query::builder().build();
+        assert_eq!(
+            world.query_sorted::<Layer>(&all),
+            vec![third, first, fourth, second]
+        );
+    }
+}
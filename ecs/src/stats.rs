@@ -0,0 +1,217 @@
+use crate::{archetype::ArchetypeIndex, storage::ComponentId, world::World};
+
+/// Snapshot of how many entities, archetypes and components a world holds
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorldStats {
+    pub entities: usize,
+    pub archetypes: Vec<ArchetypeStats>,
+    pub components: Vec<ComponentStats>,
+}
+
+impl WorldStats {
+    pub fn archetype_count(&self) -> usize {
+        self.archetypes.len()
+    }
+
+    /// Bytes allocated by all component storages
+    pub fn bytes(&self) -> usize {
+        self.components
+            .iter()
+            .map(|component| component.bytes)
+            .sum()
+    }
+
+    /// Slots of all storages that hold components no entity owns anymore
+    pub fn wasted_slots(&self) -> usize {
+        self.components
+            .iter()
+            .map(|component| component.wasted_slots)
+            .sum()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchetypeStats {
+    pub index: ArchetypeIndex,
+    pub entities: usize,
+    // Type names of the components in layout order
+    pub layout: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComponentStats {
+    pub id: ComponentId,
+    pub name: String,
+    pub len: usize,
+    pub capacity: usize,
+    // Bytes allocated by the storage, including unused capacity
+    pub bytes: usize,
+    // Components in the storage that no entity owns anymore, they are removed by `World::compact`
+    pub wasted_slots: usize,
+}
+
+impl World {
+    /// Collects statistics about entities, archetypes and component storages
+    pub fn stats(&self) -> WorldStats {
+        let archetypes = (0..self.archetypes.len() as ArchetypeIndex)
+            .map(|index| {
+                let archetype = self.archetypes.get(index);
+
+                ArchetypeStats {
+                    index,
+                    entities: archetype.entity_ids().len(),
+                    layout: archetype
+                        .layout()
+                        .clone()
+                        .into_iter()
+                        .map(|component_id| self.component_name(component_id))
+                        .collect(),
+                }
+            })
+            .collect();
+
+        let mut components: Vec<ComponentStats> = self
+            .components
+            .component_ids()
+            .map(|component_id| {
+                let storage = self.components.get_storage_raw(component_id).unwrap();
+                let owned: usize = self
+                    .archetypes
+                    .find_with_component(component_id)
+                    .iter()
+                    .map(|archetype| archetype.entity_ids().len())
+                    .sum();
+
+                ComponentStats {
+                    id: component_id,
+                    name: self.component_name(component_id),
+                    len: storage.len(),
+                    capacity: storage.capacity(),
+                    bytes: storage.capacity() * storage.item_size(),
+                    wasted_slots: storage.len() - owned,
+                }
+            })
+            .collect();
+        components.sort_by(|a, b| a.name.cmp(&b.name));
+
+        WorldStats {
+            entities: self.locations.len(),
+            archetypes,
+            components,
+        }
+    }
+
+    // Name of a component for diagnostics, the rust type name if it has one and the registered name otherwise
+    pub(crate) fn component_name(&self, component_id: ComponentId) -> String {
+        if let Some(registration) = self.registry.get(component_id) {
+            return registration.type_name().to_string();
+        }
+
+        self.components
+            .get_storage_raw(component_id)
+            .and_then(|storage| storage.type_name())
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("{:?}", component_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{alloc::Layout, any::type_name, mem, ptr::NonNull};
+
+    use crate::{
+        registry::TypeRegistration,
+        storage::{Component, ComponentId, VecStorage},
+        world::World,
+    };
+
+    #[derive(Debug)]
+    struct Position {
+        _x: f32,
+        _y: f32,
+    }
+
+    impl Component for Position {
+        type Storage = VecStorage<Self>;
+    }
+
+    #[derive(Debug)]
+    struct Sprite;
+
+    impl Component for Sprite {
+        type Storage = VecStorage<Self>;
+    }
+
+    #[test]
+    fn stats_track_archetypes_and_waste() {
+        let mut world = World::new();
+        world.register_dynamic("mana", Layout::new::<u32>(), None);
+
+        let player = world.spawn(Position { _x: 0.0, _y: 0.0 });
+        world.entry_mut(&player).add_component(Sprite);
+        let tree = world.spawn(Position { _x: 4.0, _y: 2.0 });
+        world.spawn(Sprite);
+        world.despawn(&tree);
+
+        let stats = world.stats();
+        assert_eq!(stats.entities, 2);
+        assert_eq!(stats.archetype_count(), 3);
+        assert_eq!(
+            stats.archetypes[1].layout,
+            vec![type_name::<Position>(), type_name::<Sprite>()]
+        );
+        assert_eq!(
+            stats
+                .archetypes
+                .iter()
+                .map(|archetype| archetype.entities)
+                .collect::<Vec<_>>(),
+            vec![0, 1, 1]
+        );
+
+        let names: Vec<&str> = stats.components.iter().map(|c| c.name.as_str()).collect();
+        assert!(names.contains(&"mana"));
+
+        let position = stats
+            .components
+            .iter()
+            .find(|component| component.name == type_name::<Position>())
+            .unwrap();
+        assert_eq!(position.len, 2);
+        assert_eq!(position.wasted_slots, 1);
+        assert_eq!(
+            position.bytes,
+            position.capacity * mem::size_of::<Position>()
+        );
+        assert_eq!(stats.wasted_slots(), 1);
+
+        world.compact();
+        assert_eq!(world.stats().wasted_slots(), 0);
+    }
+
+    #[test]
+    fn component_names() {
+        let mut world = World::new();
+        world.register(TypeRegistration::of::<Position>("position"));
+        let mana = world.register_dynamic("mana", Layout::new::<u32>(), None);
+
+        let caster = world.spawn(Position { _x: 0.0, _y: 0.0 });
+        let value = 3u32.to_ne_bytes();
+        unsafe {
+            world
+                .entry_mut(&caster)
+                .add_component_by_id(mana, NonNull::from(&value).cast())
+                .unwrap();
+        }
+
+        assert_eq!(world.component_name(mana), "mana");
+        assert_eq!(
+            world.component_name(ComponentId::of::<Position>()),
+            type_name::<Position>()
+        );
+        assert_eq!(
+            world.stats().archetypes.last().unwrap().layout,
+            vec![type_name::<Position>(), "mana"]
+        );
+    }
+}
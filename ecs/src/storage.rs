@@ -1,20 +1,130 @@
 use std::{
-    any::{Any, TypeId},
+    alloc::{self, Layout},
+    any::{type_name, Any, TypeId},
     collections::HashMap,
-    fmt::Debug,
+    fmt::{self, Debug},
+    mem::{self, ManuallyDrop},
+    ptr::{self, NonNull},
+    slice,
+};
+
+use crate::{
+    borrow::StorageBorrows, error::EcsError, registry::DropFn, required::RequiredComponents,
 };
 
 /// A components specific index into its storage
 pub type ComponentIndex = usize;
 
+/// Identifies a component type, either a rust type or a type that was defined at runtime
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ComponentId {
+    Static(TypeId),
+    Dynamic(u32),
+}
+
+impl ComponentId {
+    pub fn of<C: Component>() -> Self {
+        Self::Static(TypeId::of::<C>())
+    }
+
+    /// Returns the rust type of the component if it has one
+    pub fn type_id(&self) -> Option<TypeId> {
+        match self {
+            Self::Static(type_id) => Some(*type_id),
+            Self::Dynamic(_) => None,
+        }
+    }
+}
+
+impl From<TypeId> for ComponentId {
+    fn from(type_id: TypeId) -> Self {
+        Self::Static(type_id)
+    }
+}
+
 /// Defines a Component
-pub trait Component: Debug + Sized + Any {
+/// Components are `Send + Sync` so worlds can be shared between threads, values that are not can be
+/// wrapped in `NonSend` which keeps them on the thread that created them
+pub trait Component: Debug + Sized + Any + Send + Sync {
     // The Type of Storage this Component uses
     type Storage: Storage<Self>;
+
+    // Declares components that are added together with this component if an entity does not have them
+    fn required(_components: &mut RequiredComponents) {}
+}
+
+/// Part of a storage that can be used without knowing the stored component type
+///
+/// # Safety
+/// Components are stored next to each other, component i starts `i * item_size()` bytes after `as_mut_ptr()`
+/// `item_size()` is the size of the stored type, for a `Storage<T>` it is `size_of::<T>()`
+/// The first `len()` components are valid values of the stored type, `get_ptr` returns pointers into them
+/// Pointers stay valid until the storage is mutated again, distinct indices never share memory
+pub unsafe trait ErasedStorage: Debug + Any + Send + Sync {
+    // Upcasts the storage so it can be downcasted to its concrete type
+    fn as_any(&self) -> &dyn Any;
+
+    // Upcasts the storage mutable so it can be downcasted to its concrete type
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+
+    // Returns a type erased pointer to a component with a given index
+    fn get_ptr(&self, index: ComponentIndex) -> Option<NonNull<u8>>;
+
+    // Returns a pointer to the first component that can be used to write to every component
+    fn as_mut_ptr(&mut self) -> NonNull<u8>;
+
+    /// Moves the component behind `component` into the storage
+    ///
+    /// # Safety
+    /// `component` has to point to a valid value of the stored type, which must not be used or dropped afterwards
+    /// The value has to be safe to send to and share between threads, since storages are `Send` and `Sync`
+    unsafe fn push_ptr(&mut self, component: NonNull<u8>) -> ComponentIndex;
+
+    /// Drops the component with a given index and moves the component behind `component` in its place
+    ///
+    /// # Safety
+    /// Same as for `push_ptr`, additionally `index` has to be in bounds
+    unsafe fn replace_ptr(&mut self, index: ComponentIndex, component: NonNull<u8>);
+
+    /// Moves the component with a given index into `dst` and returns its index there
+    /// The last component of this storage takes over the index of the moved component
+    ///
+    /// # Safety
+    /// `dst` has to store the same component type and `index` has to be in bounds
+    unsafe fn move_to(
+        &mut self,
+        index: ComponentIndex,
+        dst: &mut dyn ErasedStorage,
+    ) -> ComponentIndex;
+
+    // Swaps two components, panics if an index is out of bounds
+    fn swap(&mut self, a: ComponentIndex, b: ComponentIndex);
+
+    // Drops every component from index `len` on
+    fn truncate(&mut self, len: usize);
+
+    // Size of a single component in bytes
+    fn item_size(&self) -> usize;
+
+    // How many components fit into the storage before it has to grow
+    fn capacity(&self) -> usize;
+
+    // Name of the stored rust type, none for components without one
+    fn type_name(&self) -> Option<&'static str>;
+
+    // Creates a new empty storage for the same component type
+    fn create_empty(&self) -> Box<dyn ErasedStorage>;
+
+    // returns how much components are stored in a storage
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
 }
 
 /// Defines a Storage that can store a single component
-pub trait Storage<T: Debug>: Debug + Any {
+pub trait Storage<T: Debug>: ErasedStorage {
     // Creates new instance of storage
     fn new() -> Self
     where
@@ -32,86 +142,179 @@ pub trait Storage<T: Debug>: Debug + Any {
     // Returns all components stored as a slice
     fn as_slice(&self) -> &[T];
 
+    // Removes a component, the last component takes over its index
+    fn swap_remove(&mut self, index: ComponentIndex) -> T;
+
     // returns how much components are stored in a storage
     fn size(&self) -> usize;
 }
 
 // Holds all the storages for every single component
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct ComponentStorages {
-    storages: HashMap<TypeId, Box<dyn Any>>,
+    storages: HashMap<ComponentId, Box<dyn ErasedStorage>>,
+    // Indexed components and whether their storage was handed out mutable since they were last indexed
+    indexed: HashMap<ComponentId, bool>,
 }
 
 impl ComponentStorages {
     pub fn new() -> Self {
         Self {
             storages: HashMap::new(),
+            indexed: HashMap::new(),
         }
     }
 
     /// Creates a new component storage
     pub fn create_storage<C: Component>(&mut self) {
-        let component_type_id = TypeId::of::<C>();
+        let component_id = ComponentId::of::<C>();
         let storage = C::Storage::new();
 
-        self.storages.insert(component_type_id, Box::from(storage));
+        self.storages.insert(component_id, Box::from(storage));
     }
 
     /// Gives back a reference to the components storage
     /// If storage of component does not exist it will be created automatically
     pub fn get_storage<C: Component>(&mut self) -> &C::Storage {
-        let type_id = TypeId::of::<C>();
-
-        if !self.storages.contains_key(&type_id) {
+        if !self.storages.contains_key(&ComponentId::of::<C>()) {
             self.create_storage::<C>();
         }
 
-        match self.storages.get(&type_id) {
-            Some(unknown_storage) => {
-                match unknown_storage.downcast_ref::<<C as Component>::Storage>() {
-                    Some(storage) => storage,
-                    None => unreachable!("We're fucked"),
-                }
-            }
-            None => unreachable!("We're fucked"),
-        }
+        self.try_get_storage::<C>()
+            .unwrap_or_else(|error| panic!("{}", error))
     }
 
     /// Gives back a mutable reference to the components storage
     /// If storage of component does not exist it will be created automatically
+    /// If the component is indexed it is indexed again before the next lookup
     pub fn get_storage_mut<C: Component>(&mut self) -> &mut <C as Component>::Storage {
-        let type_id = TypeId::of::<C>();
+        self.touch(ComponentId::of::<C>());
+        self.storage_mut::<C>()
+    }
+
+    /// Gives back a reference to the components storage if it exists
+    pub fn try_get_storage<C: Component>(&self) -> Result<&C::Storage, EcsError> {
+        let component_id = ComponentId::of::<C>();
+
+        self.storages
+            .get(&component_id)
+            .ok_or(EcsError::MissingStorage(component_id))?
+            .as_any()
+            .downcast_ref::<<C as Component>::Storage>()
+            .ok_or(EcsError::StorageTypeMismatch {
+                component: type_name::<C>(),
+            })
+    }
 
-        if !self.storages.contains_key(&type_id) {
+    /// Gives back a mutable reference to the components storage if it exists
+    /// If the component is indexed it is indexed again before the next lookup
+    pub fn try_get_storage_mut<C: Component>(
+        &mut self,
+    ) -> Result<&mut <C as Component>::Storage, EcsError> {
+        self.touch(ComponentId::of::<C>());
+        self.try_storage_mut::<C>()
+    }
+
+    // Like `get_storage_mut` but for the world, which keeps the indexes up to date itself
+    pub(crate) fn storage_mut<C: Component>(&mut self) -> &mut C::Storage {
+        if !self.storages.contains_key(&ComponentId::of::<C>()) {
             self.create_storage::<C>()
         }
 
-        match self.storages.get_mut(&type_id) {
-            Some(unknown_storage) => {
-                match unknown_storage.downcast_mut::<<C as Component>::Storage>() {
-                    Some(storage) => storage,
-                    None => unreachable!("We're fucked"),
-                }
-            }
-            None => unreachable!("We're fucked"),
+        self.try_storage_mut::<C>()
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub(crate) fn try_storage_mut<C: Component>(&mut self) -> Result<&mut C::Storage, EcsError> {
+        let component_id = ComponentId::of::<C>();
+
+        self.storages
+            .get_mut(&component_id)
+            .ok_or(EcsError::MissingStorage(component_id))?
+            .as_any_mut()
+            .downcast_mut::<<C as Component>::Storage>()
+            .ok_or(EcsError::StorageTypeMismatch {
+                component: type_name::<C>(),
+            })
+    }
+
+    /// Creates the storage of a component that was defined at runtime
+    pub fn create_blob_storage(
+        &mut self,
+        component_id: ComponentId,
+        layout: Layout,
+        drop: Option<DropFn>,
+    ) {
+        let storage = BlobStorage::new(layout, drop);
+        self.storages.insert(component_id, Box::from(storage));
+    }
+
+    /// Adds a storage that was created without knowing its component type
+    pub fn insert_storage_raw(
+        &mut self,
+        component_id: ComponentId,
+        storage: Box<dyn ErasedStorage>,
+    ) {
+        self.storages.insert(component_id, storage);
+    }
+
+    /// Allows borrowing several storages at the same time, each one is borrow checked at runtime
+    /// Every indexed component is indexed again before the next lookup
+    pub fn borrows(&mut self) -> StorageBorrows<'_> {
+        for touched in self.indexed.values_mut() {
+            *touched = true;
         }
+
+        StorageBorrows::new(self.storages.iter_mut())
+    }
+
+    /// Returns the ids of every component that has a storage
+    pub fn component_ids(&self) -> impl Iterator<Item = ComponentId> + '_ {
+        self.storages.keys().copied()
     }
 
     /// Reference to storage but type is unknown
-    pub fn get_storage_raw(&self, type_id: TypeId) -> &Box<dyn Any> {
-        match self.storages.get(&type_id) {
-            Some(unknown_storage) => unknown_storage,
-            None => unreachable!("We're fucked"),
-        }
+    pub fn get_storage_raw(&self, component_id: ComponentId) -> Option<&dyn ErasedStorage> {
+        self.storages
+            .get(&component_id)
+            .map(|unknown_storage| unknown_storage.as_ref())
     }
 
     /// Mutable Reference to storage but type is unknown
-    pub fn get_storage_raw_mut(&mut self, type_id: TypeId) -> &mut Box<dyn Any> {
-        match self.storages.get_mut(&type_id) {
-            Some(unknown_storage) => unknown_storage,
-            None => unreachable!("We're fucked"),
+    /// If the component is indexed it is indexed again before the next lookup
+    pub fn get_storage_raw_mut(
+        &mut self,
+        component_id: ComponentId,
+    ) -> Option<&mut dyn ErasedStorage> {
+        self.touch(component_id);
+        self.storage_raw_mut(component_id)
+    }
+
+    pub(crate) fn storage_raw_mut(
+        &mut self,
+        component_id: ComponentId,
+    ) -> Option<&mut dyn ErasedStorage> {
+        match self.storages.get_mut(&component_id) {
+            Some(unknown_storage) => Some(unknown_storage.as_mut()),
+            None => None,
+        }
+    }
+
+    // Remembers that the storage of an indexed component may be changed without the world knowing
+    fn touch(&mut self, component_id: ComponentId) {
+        if let Some(touched) = self.indexed.get_mut(&component_id) {
+            *touched = true;
         }
     }
+
+    pub(crate) fn set_indexed(&mut self, component_id: ComponentId) {
+        self.indexed.insert(component_id, false);
+    }
+
+    // Returns if the storage of an indexed component was handed out mutable since the last call
+    pub(crate) fn take_touched(&mut self, component_id: ComponentId) -> bool {
+        self.indexed.get_mut(&component_id).is_some_and(mem::take)
+    }
 }
 
 // Actual implementation of a storage based on a vector
@@ -120,6 +323,75 @@ pub struct VecStorage<T: Component> {
     storage: Vec<T>,
 }
 
+// Safety: The components are the elements of a `Vec<T>`
+unsafe impl<T> ErasedStorage for VecStorage<T>
+where
+    T: Component,
+{
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn get_ptr(&self, index: ComponentIndex) -> Option<NonNull<u8>> {
+        self.storage
+            .get(index)
+            .map(|component| NonNull::from(component).cast())
+    }
+
+    fn as_mut_ptr(&mut self) -> NonNull<u8> {
+        NonNull::new(self.storage.as_mut_ptr()).unwrap().cast()
+    }
+
+    unsafe fn push_ptr(&mut self, component: NonNull<u8>) -> ComponentIndex {
+        self.push_component(component.cast::<T>().as_ptr().read())
+    }
+
+    unsafe fn replace_ptr(&mut self, index: ComponentIndex, component: NonNull<u8>) {
+        self.storage[index] = component.cast::<T>().as_ptr().read();
+    }
+
+    unsafe fn move_to(
+        &mut self,
+        index: ComponentIndex,
+        dst: &mut dyn ErasedStorage,
+    ) -> ComponentIndex {
+        let component = ManuallyDrop::new(self.swap_remove(index));
+        dst.push_ptr(NonNull::from(&*component).cast())
+    }
+
+    fn swap(&mut self, a: ComponentIndex, b: ComponentIndex) {
+        self.storage.swap(a, b);
+    }
+
+    fn truncate(&mut self, len: usize) {
+        self.storage.truncate(len);
+    }
+
+    fn item_size(&self) -> usize {
+        mem::size_of::<T>()
+    }
+
+    fn capacity(&self) -> usize {
+        self.storage.capacity()
+    }
+
+    fn type_name(&self) -> Option<&'static str> {
+        Some(type_name::<T>())
+    }
+
+    fn create_empty(&self) -> Box<dyn ErasedStorage> {
+        Box::new(Self::new())
+    }
+
+    fn len(&self) -> usize {
+        self.storage.len()
+    }
+}
+
 impl<T> Storage<T> for VecStorage<T>
 where
     T: Component,
@@ -155,4 +427,209 @@ where
     fn as_slice(&self) -> &[T] {
         self.storage.as_slice()
     }
+
+    fn swap_remove(&mut self, index: ComponentIndex) -> T {
+        self.storage.swap_remove(index)
+    }
+}
+
+// Storage for components without a rust type, the components are stored as raw bytes
+pub struct BlobStorage {
+    layout: Layout,
+    drop: Option<DropFn>,
+    data: NonNull<u8>,
+    len: usize,
+    capacity: usize,
+}
+
+// Safety: Dynamic components have to be `Send + Sync` just like components with a rust type
+unsafe impl Send for BlobStorage {}
+unsafe impl Sync for BlobStorage {}
+
+impl BlobStorage {
+    pub fn new(layout: Layout, drop: Option<DropFn>) -> Self {
+        let layout = layout.pad_to_align();
+
+        // Zero sized components never need memory
+        let capacity = if layout.size() == 0 { usize::MAX } else { 0 };
+
+        Self {
+            layout,
+            drop,
+            data: NonNull::new(ptr::without_provenance_mut(layout.align())).unwrap(),
+            len: 0,
+            capacity,
+        }
+    }
+
+    /// Memory layout of a single component
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    // returns how much components are stored in a storage
+    pub fn size(&self) -> usize {
+        self.len
+    }
+
+    /// Returns the raw bytes of a component with a given index
+    pub fn get_bytes(&self, index: ComponentIndex) -> Option<&[u8]> {
+        self.get_ptr(index)
+            .map(|ptr| unsafe { slice::from_raw_parts(ptr.as_ptr(), self.layout.size()) })
+    }
+
+    /// Returns the raw bytes of a component with a given index mutable
+    pub fn get_bytes_mut(&mut self, index: ComponentIndex) -> Option<&mut [u8]> {
+        self.get_ptr(index)
+            .map(|ptr| unsafe { slice::from_raw_parts_mut(ptr.as_ptr(), self.layout.size()) })
+    }
+
+    fn array_layout(&self, capacity: usize) -> Layout {
+        Layout::from_size_align(self.layout.size() * capacity, self.layout.align())
+            .expect("Blob storage capacity overflow")
+    }
+
+    fn grow(&mut self) {
+        let new_capacity = (self.capacity * 2).max(4);
+        let new_layout = self.array_layout(new_capacity);
+
+        let data = unsafe {
+            if self.capacity == 0 {
+                alloc::alloc(new_layout)
+            } else {
+                alloc::realloc(
+                    self.data.as_ptr(),
+                    self.array_layout(self.capacity),
+                    new_layout.size(),
+                )
+            }
+        };
+
+        self.data = NonNull::new(data).unwrap_or_else(|| alloc::handle_alloc_error(new_layout));
+        self.capacity = new_capacity;
+    }
+}
+
+// Safety: Components are written at multiples of the padded layout size into one allocation
+unsafe impl ErasedStorage for BlobStorage {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn get_ptr(&self, index: ComponentIndex) -> Option<NonNull<u8>> {
+        if index < self.len {
+            Some(unsafe { self.data.add(index * self.layout.size()) })
+        } else {
+            None
+        }
+    }
+
+    fn as_mut_ptr(&mut self) -> NonNull<u8> {
+        self.data
+    }
+
+    unsafe fn push_ptr(&mut self, component: NonNull<u8>) -> ComponentIndex {
+        if self.len == self.capacity {
+            self.grow();
+        }
+
+        let component_index = self.len;
+        let dst = self.data.add(component_index * self.layout.size());
+        ptr::copy_nonoverlapping(component.as_ptr(), dst.as_ptr(), self.layout.size());
+        self.len += 1;
+
+        component_index
+    }
+
+    unsafe fn replace_ptr(&mut self, index: ComponentIndex, component: NonNull<u8>) {
+        let dst = self.get_ptr(index).expect("Component index out of bounds");
+
+        if let Some(drop) = self.drop {
+            drop(dst);
+        }
+        ptr::copy_nonoverlapping(component.as_ptr(), dst.as_ptr(), self.layout.size());
+    }
+
+    unsafe fn move_to(
+        &mut self,
+        index: ComponentIndex,
+        dst: &mut dyn ErasedStorage,
+    ) -> ComponentIndex {
+        let component = self.get_ptr(index).expect("Component index out of bounds");
+        let dst_index = dst.push_ptr(component);
+
+        self.len -= 1;
+        if index != self.len {
+            let last = self.data.add(self.len * self.layout.size());
+            ptr::copy_nonoverlapping(last.as_ptr(), component.as_ptr(), self.layout.size());
+        }
+
+        dst_index
+    }
+
+    fn swap(&mut self, a: ComponentIndex, b: ComponentIndex) {
+        let a = self.get_ptr(a).expect("Component index out of bounds");
+        let b = self.get_ptr(b).expect("Component index out of bounds");
+
+        if a != b {
+            unsafe { ptr::swap_nonoverlapping(a.as_ptr(), b.as_ptr(), self.layout.size()) }
+        }
+    }
+
+    fn truncate(&mut self, len: usize) {
+        while self.len > len {
+            self.len -= 1;
+
+            if let Some(drop) = self.drop {
+                unsafe { drop(self.data.add(self.len * self.layout.size())) }
+            }
+        }
+    }
+
+    fn item_size(&self) -> usize {
+        self.layout.size()
+    }
+
+    fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    fn type_name(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn create_empty(&self) -> Box<dyn ErasedStorage> {
+        Box::new(Self::new(self.layout, self.drop))
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl Debug for BlobStorage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BlobStorage")
+            .field("layout", &self.layout)
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+impl Drop for BlobStorage {
+    fn drop(&mut self) {
+        if let Some(drop) = self.drop {
+            for index in 0..self.len {
+                unsafe { drop(self.data.add(index * self.layout.size())) }
+            }
+        }
+
+        if self.layout.size() != 0 && self.capacity != 0 {
+            unsafe { alloc::dealloc(self.data.as_ptr(), self.array_layout(self.capacity)) }
+        }
+    }
 }
@@ -0,0 +1,48 @@
+use std::time::{Duration, Instant};
+
+/// Resource that tracks how much time passed between frames
+/// It is updated once per frame, before the schedule runs
+#[derive(Debug, Clone, Default)]
+pub struct Time {
+    delta: Duration,
+    elapsed: Duration,
+    last_update: Option<Instant>,
+}
+
+impl Time {
+    pub fn new() -> Self {
+        Self {
+            delta: Duration::ZERO,
+            elapsed: Duration::ZERO,
+            last_update: None,
+        }
+    }
+
+    /// Measures the time since the last update, the first update has a delta of zero
+    pub fn update(&mut self) {
+        let now = Instant::now();
+        let delta = match self.last_update {
+            Some(last_update) => now - last_update,
+            None => Duration::ZERO,
+        };
+
+        self.advance(delta);
+        self.last_update = Some(now);
+    }
+
+    /// Advances the time by a fixed amount, useful for tests and replays
+    pub fn advance(&mut self, delta: Duration) {
+        self.delta = delta;
+        self.elapsed += delta;
+    }
+
+    /// Time between the last two updates
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    /// Time since the first update
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+}
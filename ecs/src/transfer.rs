@@ -0,0 +1,420 @@
+use std::{cmp::Reverse, collections::HashMap, ptr};
+
+use crate::{
+    archetype::{Entity, EntityLayout},
+    error::EcsError,
+    hooks::HookKind,
+    location::EntityLocation,
+    storage::{ComponentId, ComponentIndex},
+    world::World,
+};
+
+impl World {
+    /// Moves an entity with all of its components into another world and returns its new handle there
+    /// The entity is detached from its hierarchy first, because its parent and children stay behind
+    /// Panics if the entity does not exist
+    pub fn move_entity(&mut self, entity: &Entity, dst: &mut World) -> Entity {
+        self.try_move_entity(entity, dst)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Moves an entity with all of its components into another world and returns its new handle there
+    /// Fails without changing the entity if a dynamic component can't be stored in the other world
+    pub fn try_move_entity(
+        &mut self,
+        entity: &Entity,
+        dst: &mut World,
+    ) -> Result<Entity, EcsError> {
+        self.check_entity(entity)?;
+
+        let mut dst_component_ids = HashMap::new();
+        for component_id in self.entry(entity).archetype().layout().clone() {
+            dst_component_ids.insert(component_id, dst.import_component(self, component_id)?);
+        }
+
+        // Detaching removes the hierarchy components, so the layout is read afterwards
+        self.detach(entity);
+
+        let archetype = self
+            .archetypes
+            .find_from_entity_mut(entity)
+            .expect("Entity has no archetype!");
+        archetype.unassigne_entity(entity);
+
+        let layout = archetype.layout().clone();
+        let locations = self
+            .locations
+            .remove(entity)
+            .expect("Entity has no locations!");
+
+        let mut new_layout = EntityLayout::new();
+        let mut indices = Vec::new();
+
+        for (component_id, location) in layout.into_iter().zip(locations) {
+            let dst_component_id = dst_component_ids[&component_id];
+            let storage = self
+                .components
+                .storage_raw_mut(component_id)
+                .expect("Component has no storage!");
+            let dst_storage = dst
+                .components
+                .storage_raw_mut(dst_component_id)
+                .expect("Component has no storage!");
+
+            let last = storage.len() - 1;
+            // Safety: Both storages belong to the same component and the location is in bounds
+            let index = unsafe { storage.move_to(location.component(), dst_storage) };
+
+            // The last component of the storage took over the index of the moved one
+            if location.component() != last {
+                self.relocate_component(component_id, last, location.component());
+            }
+
+            new_layout.register_component_id(dst_component_id);
+            indices.push(index);
+        }
+
+        let new_entity = dst.alloc_entity();
+        dst.insert_moved(new_entity, new_layout.clone(), indices);
+
+        for component_id in new_layout {
+            dst.trigger_hooks(HookKind::Add, component_id, &new_entity);
+        }
+
+        Ok(new_entity)
+    }
+
+    /// Moves every entity of another world into this one
+    /// Returns which new entity every entity of the other world became
+    /// Parent and child relations between the moved entities are kept
+    /// Panics if a dynamic component of the other world can't be stored in this world
+    pub fn merge(&mut self, other: World) -> HashMap<Entity, Entity> {
+        self.try_merge(other)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Moves every entity of another world into this one
+    /// Returns which new entity every entity of the other world became
+    /// Fails before anything is moved if a dynamic component can't be stored in this world
+    pub fn try_merge(&mut self, mut other: World) -> Result<HashMap<Entity, Entity>, EcsError> {
+        let mut component_ids = HashMap::new();
+        for component_id in other.components.component_ids().collect::<Vec<_>>() {
+            component_ids.insert(component_id, self.import_component(&other, component_id)?);
+        }
+
+        let mut old_entities: Vec<Entity> = other.locations.iter().map(|(e, _)| *e).collect();
+        old_entities.sort_by_key(Entity::id);
+
+        let mut entities = HashMap::new();
+        let mut layouts = Vec::new();
+        let mut moves: HashMap<ComponentId, Vec<(ComponentIndex, usize, usize)>> = HashMap::new();
+
+        for old_entity in old_entities {
+            let new_entity = self.alloc_entity();
+            entities.insert(old_entity, new_entity);
+
+            let locations = other
+                .locations
+                .remove(&old_entity)
+                .expect("Entity has no locations!");
+            let layout = match locations.first() {
+                Some(location) => other.archetypes.get(location.archetype()).layout().clone(),
+                None => EntityLayout::new(),
+            };
+
+            let mut new_layout = EntityLayout::new();
+            for (position, (component_id, location)) in
+                layout.into_iter().zip(&locations).enumerate()
+            {
+                let dst_component_id = component_ids[&component_id];
+                new_layout.register_component_id(dst_component_id);
+
+                moves.entry(component_id).or_default().push((
+                    location.component(),
+                    layouts.len(),
+                    position,
+                ));
+            }
+
+            layouts.push((new_entity, new_layout, vec![0; locations.len()]));
+        }
+
+        for (component_id, mut components) in moves {
+            let storage = other
+                .components
+                .storage_raw_mut(component_id)
+                .expect("Component has no storage!");
+            let dst_storage = self
+                .components
+                .storage_raw_mut(component_ids[&component_id])
+                .expect("Component has no storage!");
+
+            // Moving from the back means only unused components take over the indices of moved ones
+            components.sort_by_key(|component| Reverse(component.0));
+            for (index, entity, position) in components {
+                // Safety: Both storages belong to the same component and the index is in bounds
+                layouts[entity].2[position] = unsafe { storage.move_to(index, dst_storage) };
+            }
+        }
+
+        for (entity, layout, indices) in layouts.iter().cloned() {
+            self.insert_moved(entity, layout, indices);
+        }
+
+        self.remap_hierarchy(&entities);
+
+        for (entity, layout, _) in layouts {
+            for component_id in layout {
+                self.trigger_hooks(HookKind::Add, component_id, &entity);
+            }
+        }
+
+        Ok(entities)
+    }
+
+    // Makes sure a component of another world can be stored in this world and returns its id here
+    // Dynamic components are matched by their registered name, their layouts and drop functions have to match too
+    fn import_component(
+        &mut self,
+        source: &World,
+        component_id: ComponentId,
+    ) -> Result<ComponentId, EcsError> {
+        let registration = source.registry.get(component_id);
+
+        let dst_component_id = match component_id {
+            ComponentId::Static(_) => component_id,
+            ComponentId::Dynamic(_) => {
+                let registration = registration.expect("Dynamic component is not registered!");
+
+                match self.registry.get_by_name(registration.name()) {
+                    Some(existing) => {
+                        let same_drop = match (existing.drop_fn(), registration.drop_fn()) {
+                            (Some(a), Some(b)) => ptr::fn_addr_eq(a, b),
+                            (a, b) => a.is_none() && b.is_none(),
+                        };
+                        if existing.layout() != registration.layout() || !same_drop {
+                            return Err(EcsError::IncompatibleComponent {
+                                component: registration.name().to_string(),
+                            });
+                        }
+
+                        existing.id()
+                    }
+                    None => self.register_dynamic(
+                        registration.name(),
+                        registration.layout(),
+                        registration.drop_fn(),
+                    ),
+                }
+            }
+        };
+
+        if let Some(registration) = registration {
+            if self.registry.get(dst_component_id).is_none() {
+                self.register(registration.clone());
+            }
+        }
+
+        if self.components.get_storage_raw(dst_component_id).is_none() {
+            let storage = source
+                .components
+                .get_storage_raw(component_id)
+                .expect("Component has no storage!");
+            self.components
+                .insert_storage_raw(dst_component_id, storage.create_empty());
+        }
+
+        Ok(dst_component_id)
+    }
+
+    // Assigns an entity whose components were moved into the storages already
+    pub(crate) fn insert_moved(
+        &mut self,
+        entity: Entity,
+        layout: EntityLayout,
+        indices: Vec<ComponentIndex>,
+    ) {
+        let archetype = self.archetypes.find_or_create_from_layout(layout);
+        archetype.assigne_entity(&entity);
+        let archetype_index = archetype.index();
+
+        let locations = indices
+            .into_iter()
+            .map(|index| EntityLocation::new(archetype_index, index, self.change_tick()))
+            .collect();
+        self.locations.insert(entity, locations);
+    }
+
+    // Points the location that refers to index `from` in the storage of a component to `to`
+    pub(crate) fn relocate_component(
+        &mut self,
+        component_id: ComponentId,
+        from: ComponentIndex,
+        to: ComponentIndex,
+    ) {
+        for archetype in self.archetypes.find_with_component(component_id) {
+            let index = archetype.layout().index_of(component_id).unwrap();
+
+            for id in archetype.entity_ids() {
+                let location = &mut self
+                    .locations
+                    .get_mut(&Entity(*id))
+                    .expect("Entity has no locations!")[index];
+
+                if location.component() == from {
+                    location.set_component(to);
+                    return;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::alloc::Layout;
+
+    use crate::{
+        error::EcsError,
+        storage::{Component, VecStorage},
+        world::World,
+    };
+
+    #[derive(Debug, PartialEq)]
+    struct Position(f32, f32);
+
+    impl Component for Position {
+        type Storage = VecStorage<Self>;
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Name(String);
+
+    impl Component for Name {
+        type Storage = VecStorage<Self>;
+    }
+
+    #[test]
+    fn move_entity_between_worlds() {
+        let mut staging = World::new();
+        let mut live = World::new();
+        live.spawn(Position(9.0, 9.0));
+
+        let first = staging.spawn(Position(1.0, 1.0));
+        let second = staging.spawn(Position(2.0, 2.0));
+        staging
+            .entry_mut(&first)
+            .add_component(Name("first".to_string()));
+
+        let moved = staging.move_entity(&first, &mut live);
+
+        assert!(staging.try_entry(&first).is_err());
+        assert_eq!(
+            *staging.entry(&second).get_component::<Position>().unwrap(),
+            Position(2.0, 2.0)
+        );
+        assert_eq!(
+            *live.entry(&moved).get_component::<Name>().unwrap(),
+            Name("first".to_string())
+        );
+        assert_eq!(
+            *live.entry(&moved).get_component::<Position>().unwrap(),
+            Position(1.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn move_parented_entity() {
+        let mut staging = World::new();
+        let root = staging.spawn(Position(0.0, 0.0));
+        let child = staging.spawn(Position(1.0, 1.0));
+        staging.set_parent(&child, &root);
+        staging
+            .entry_mut(&child)
+            .add_component(Name("child".to_string()));
+
+        let mut live = World::new();
+        let moved = staging.move_entity(&child, &mut live);
+
+        let entry = live.entry(&moved);
+        assert_eq!(entry.archetype().layout().clone().into_iter().count(), 2);
+        assert_eq!(
+            *entry.get_component::<Position>().unwrap(),
+            Position(1.0, 1.0)
+        );
+        assert_eq!(
+            *entry.get_component::<Name>().unwrap(),
+            Name("child".to_string())
+        );
+        assert!(staging.children(&root).is_empty());
+    }
+
+    #[test]
+    fn merge_keeps_hierarchy_and_dynamic_components() {
+        let mut staging = World::new();
+        let mana = staging.register_dynamic("mana", Layout::new::<u32>(), None);
+
+        let root = staging.spawn(Position(0.0, 0.0));
+        let child = staging.spawn(Position(1.0, 0.0));
+        staging.set_parent(&child, &root);
+
+        let value = 7u32.to_ne_bytes();
+        unsafe {
+            staging
+                .entry_mut(&child)
+                .add_component_by_id(mana, std::ptr::NonNull::from(&value).cast())
+                .unwrap();
+        }
+
+        let mut live = World::new();
+        live.register_dynamic("stamina", Layout::new::<u8>(), None);
+        live.spawn(Position(5.0, 5.0));
+
+        let entities = live.merge(staging);
+        let (root, child) = (entities[&root], entities[&child]);
+
+        assert_eq!(live.children(&root), &[child]);
+        assert_eq!(
+            live.entry(&child)
+                .get_component_bytes(live.component_id("mana").unwrap()),
+            Some(&value[..])
+        );
+        assert_eq!(
+            *live.entry(&child).get_component::<Position>().unwrap(),
+            Position(1.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn incompatible_dynamic_components_are_not_moved() {
+        let mut staging = World::new();
+        let mana = staging.register_dynamic("mana", Layout::new::<u64>(), None);
+        let caster = staging.spawn(Position(0.0, 0.0));
+        let value = 7u64.to_ne_bytes();
+        unsafe {
+            staging
+                .entry_mut(&caster)
+                .add_component_by_id(mana, std::ptr::NonNull::from(&value).cast())
+                .unwrap();
+        }
+
+        let mut live = World::new();
+        live.register_dynamic("mana", Layout::new::<u8>(), None);
+
+        let error = EcsError::IncompatibleComponent {
+            component: "mana".to_string(),
+        };
+        assert_eq!(
+            staging.try_move_entity(&caster, &mut live),
+            Err(error.clone())
+        );
+        assert_eq!(
+            staging.entry(&caster).get_component_bytes(mana),
+            Some(&value[..])
+        );
+        assert!(live.is_empty());
+
+        assert_eq!(live.try_merge(staging), Err(error));
+        assert!(live.is_empty());
+    }
+}
@@ -1,97 +1,409 @@
-use std::collections::HashMap;
+use std::{
+    alloc::Layout,
+    any::type_name,
+    collections::HashMap,
+    sync::atomic::{AtomicU32, Ordering},
+};
 
 use crate::{
-    archetype::{ArchetypeStorage, EntityLayout},
+    archetype::{Archetype, ArchetypeStorage, EntityLayout},
     entry::{EntryMut, EntryRef},
-    location::EntityLocation,
-    storage::Storage,
+    error::EcsError,
+    hooks::{HookKind, Hooks},
+    index::Indexes,
+    query::Query,
+    storage::{ComponentId, ErasedStorage, Storage},
 };
 
 use crate::{
     archetype::Entity,
-    location::LocationMap,
+    location::{LocationMap, Tick},
+    registry::{ComponentRef, DropFn, TypeRegistration, TypeRegistry},
+    required::RequiredComponents,
+    resource::{NonSendResources, Resources},
     storage::{Component, ComponentStorages},
 };
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct World {
-    entity_id: u32,
+    entity_id: AtomicU32,
+    // Entities below this id exist, the ones above were only reserved yet
+    materialized: u32,
+    change_tick: Tick,
     pub locations: LocationMap,
     pub archetypes: ArchetypeStorage,
     pub components: ComponentStorages,
+    pub registry: TypeRegistry,
+    pub hooks: Hooks,
+    pub resources: Resources,
+    // Resources that are leaked if the world is dropped on another thread than the one that inserted them
+    pub non_send_resources: NonSendResources,
+    pub indexes: Indexes,
 }
 
 impl World {
     pub fn new() -> Self {
         Self {
-            entity_id: 0,
+            entity_id: AtomicU32::new(0),
+            materialized: 0,
+            change_tick: 0,
             locations: LocationMap::new(),
             archetypes: ArchetypeStorage::new(),
             components: ComponentStorages::new(),
+            registry: TypeRegistry::new(),
+            hooks: Hooks::new(),
+            resources: Resources::new(),
+            non_send_resources: NonSendResources::new(),
+            indexes: Indexes::new(),
         }
     }
 
+    /// Current tick that is stored with every added or mutably accessed component
+    pub fn change_tick(&self) -> Tick {
+        self.change_tick
+    }
+
+    /// Advances the world to the next tick and returns the new tick
+    /// Components changed before can be detected by comparing with the previous tick
+    pub fn increment_change_tick(&mut self) -> Tick {
+        self.refresh_indexes();
+        self.change_tick += 1;
+        self.change_tick
+    }
+
     /// Creates new enity and adds one component to it
     pub fn spawn<C: Component>(&mut self, component: C) -> Entity {
-        let entity = Entity(self.entity_id);
+        let entity = self.alloc_entity();
 
         let mut layout = EntityLayout::new();
         layout.register_component::<C>();
 
+        // Push new component into storage
+        let storage = self.components.storage_mut::<C>();
+        let mut added = vec![(ComponentId::of::<C>(), storage.push_component(component))];
+
+        // Required components are created for the same layout, nothing is allocated for components without any
+        added.extend(
+            RequiredComponents::of::<C>().insert_missing(&mut layout, &mut self.components),
+        );
+
         // If there is no archetype with that specific layout there is a new one created
-        let archetype = match self.archetypes.find_from_layout_mut(&layout) {
-            Some(archetype) => {
-                archetype.assigne_entity(&entity);
-                archetype
-            }
-            None => {
-                let archetype = self.archetypes.create_from_layout(layout);
-                archetype.assigne_entity(&entity);
-                archetype
+        self.insert_moved(
+            entity,
+            layout,
+            added.iter().map(|(_, index)| *index).collect(),
+        );
+
+        for (component_id, _) in added {
+            self.trigger_hooks(HookKind::Add, component_id, &entity);
+        }
+
+        entity
+    }
+
+    /// Removes an entity and detaches it from the hierarchy, returns false if it does not exist
+    /// Its components stay in their storages until the storages are cleaned up
+    pub fn despawn(&mut self, entity: &Entity) -> bool {
+        if !self.locations.contains(entity) {
+            return false;
+        }
+
+        self.detach(entity);
+
+        self.archetypes
+            .find_from_entity_mut(entity)
+            .expect("Entity has no archetype!")
+            .unassigne_entity(entity);
+        self.locations.remove(entity);
+
+        true
+    }
+
+    // Hands out a new unique entity
+    pub(crate) fn alloc_entity(&mut self) -> Entity {
+        self.flush();
+
+        let entity_id = self.entity_id.get_mut();
+        let entity = Entity(*entity_id);
+        *entity_id += 1;
+        self.materialized = *entity_id;
+
+        entity
+    }
+
+    /// Reserves a unique entity without changing the world, it can be called from many threads at once
+    /// The entity exists without components once the world is flushed
+    pub fn reserve_entity(&self) -> Entity {
+        Entity(self.entity_id.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// Turns every reserved entity into an existing entity without components
+    pub fn flush(&mut self) {
+        let reserved = *self.entity_id.get_mut();
+
+        for entity_id in self.materialized..reserved {
+            self.insert_empty(Entity(entity_id));
+        }
+
+        self.materialized = reserved;
+    }
+
+    /// Creates a new entity without any components
+    pub fn spawn_empty(&mut self) -> Entity {
+        let entity = self.alloc_entity();
+        self.insert_empty(entity);
+
+        entity
+    }
+
+    // Assigns an entity to the archetype without components
+    fn insert_empty(&mut self, entity: Entity) {
+        self.archetypes
+            .find_or_create_from_layout(EntityLayout::new())
+            .assigne_entity(&entity);
+        self.locations.insert(entity, Vec::new());
+    }
+
+    // Runs the despawn and remove hooks of an entity and detaches it from the hierarchy
+    pub(crate) fn detach(&mut self, entity: &Entity) {
+        self.trigger_despawn_hooks(entity);
+
+        // Unlinking removes `Parent` and `Children` which triggers their remove hooks once
+        self.remove_parent(entity);
+        for child in self.children(entity).to_vec() {
+            self.remove_parent(&child);
+        }
+
+        let layout = self
+            .archetypes
+            .find_from_entity(entity)
+            .expect("Entity has no archetype!")
+            .layout()
+            .clone();
+        for component_id in layout {
+            self.trigger_hooks(HookKind::Remove, component_id, entity);
+        }
+    }
+
+    /// Returns a reference to component `C` of an entity
+    pub fn get_component<C: Component>(&self, entity: &Entity) -> Option<&C> {
+        let archetype = self.archetypes.find_from_entity(entity)?;
+        let index = archetype.layout().index_of(ComponentId::of::<C>())?;
+
+        let location = &self.locations.get(entity)?[index];
+        let storage = self.components.try_get_storage::<C>().ok()?;
+
+        storage.get_component(location.component())
+    }
+
+    /// Returns a mutable reference to component `C` of an entity and marks it as changed
+    pub fn get_component_mut<C: Component>(&mut self, entity: &Entity) -> Option<&mut C> {
+        let archetype = self.archetypes.find_from_entity(entity)?;
+        let index = archetype.layout().index_of(ComponentId::of::<C>())?;
+
+        let location = &mut self.locations.get_mut(entity)?[index];
+        location.set_changed(self.change_tick);
+        let location = location.component();
+        self.indexes.mark_dirty(ComponentId::of::<C>(), *entity);
+        let storage = self.components.try_storage_mut::<C>().ok()?;
+
+        storage.get_component_mut(location)
+    }
+
+    /// Returns component `C` of several entities mutable at the same time and marks them as changed
+    /// Fails if an entity is passed twice, does not exist or does not have the component
+    pub fn get_many_mut<C: Component, const N: usize>(
+        &mut self,
+        entities: [Entity; N],
+    ) -> Result<[&mut C; N], EcsError> {
+        let component_id = ComponentId::of::<C>();
+
+        let mut positions = [0; N];
+        for (index, entity) in entities.iter().enumerate() {
+            if entities[..index].contains(entity) {
+                return Err(EcsError::DuplicateEntity(*entity));
             }
-        };
+            self.check_entity(entity)?;
+
+            positions[index] = self
+                .archetypes
+                .find_from_entity(entity)
+                .and_then(|archetype| archetype.layout().index_of(component_id))
+                .ok_or(EcsError::MissingComponent {
+                    entity: *entity,
+                    component: type_name::<C>(),
+                })?;
+        }
 
-        // Get component type specific storage
-        let storage = self.components.get_storage_mut::<C>();
+        let mut indices = [0; N];
+        for (index, entity) in entities.iter().enumerate() {
+            let location = &mut self.locations.get_mut(entity).unwrap()[positions[index]];
+            location.set_changed(self.change_tick);
+            indices[index] = location.component();
+            self.indexes.mark_dirty(component_id, *entity);
+        }
 
-        // Push new component into storage
-        let component_index = storage.push_component(component);
+        // Entities are distinct, so their components are at distinct indices
+        for (index, component) in indices.iter().enumerate() {
+            assert!(
+                !indices[..index].contains(component),
+                "Components of distinct entities share an index!"
+            );
+        }
 
-        let location = EntityLocation::new(archetype.index(), component_index);
-        // Insert the component index from storage into location map
-        self.locations.insert(entity, vec![location]);
+        let storage = self.components.try_storage_mut::<C>()?;
+        assert!(
+            indices.iter().all(|index| *index < storage.len()),
+            "Component index out of bounds"
+        );
 
-        self.entity_id += 1;
+        // Every reference is derived from one base pointer, so creating one does not invalidate the others
+        let size = storage.item_size();
+        let base = storage.as_mut_ptr();
 
-        entity.clone()
+        // Safety: `ErasedStorage` guarantees the stride and the indices are in bounds and pairwise distinct,
+        // so the references point to whole components and don't alias
+        Ok(indices.map(|index| unsafe { &mut *base.add(index * size).cast::<C>().as_ptr() }))
+    }
+
+    /// Checks that an entity is alive, tells if it never existed or was despawned otherwise
+    pub fn check_entity(&self, entity: &Entity) -> Result<(), EcsError> {
+        if self.contains(entity) {
+            Ok(())
+        } else if entity.id() < self.materialized {
+            Err(EcsError::StaleHandle(*entity))
+        } else {
+            Err(EcsError::NoSuchEntity(*entity))
+        }
     }
 
     /// Returns an entry for a enity to provides read and write access for entitys components
+    /// Panics if the entity does not exist
     pub fn entry_mut<'a>(&'a mut self, entity: &'a Entity) -> EntryMut<'a> {
-        EntryMut::new(
-            entity,
-            &mut self.archetypes,
-            &mut self.components,
-            &mut self.locations,
-        )
+        self.try_entry_mut(entity)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Returns an entry for a enity to provides read and write access for entitys components
+    /// Reserved entities are flushed first so they can be filled right away
+    pub fn try_entry_mut<'a>(&'a mut self, entity: &'a Entity) -> Result<EntryMut<'a>, EcsError> {
+        self.flush();
+        self.check_entity(entity)?;
+
+        Ok(EntryMut::new(entity, self))
     }
 
     /// Returns an entry for a enity to provides read only access for entitys components
+    /// Panics if the entity does not exist
     pub fn entry<'a>(&'a self, entity: &'a Entity) -> EntryRef<'a> {
-        let archetype = self.archetypes.find_from_entity(entity).unwrap();
+        self.try_entry(entity)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Returns an entry for a enity to provides read only access for entitys components
+    pub fn try_entry<'a>(&'a self, entity: &'a Entity) -> Result<EntryRef<'a>, EcsError> {
+        self.check_entity(entity)?;
+
+        let archetype = self
+            .archetypes
+            .find_from_entity(entity)
+            .ok_or(EcsError::NoSuchEntity(*entity))?;
 
         let mut components = HashMap::new();
         for component_id in archetype.layout().clone().into_iter() {
-            let unknown_storage = self.components.get_storage_raw(component_id);
+            let unknown_storage = self
+                .components
+                .get_storage_raw(component_id)
+                .ok_or(EcsError::MissingStorage(component_id))?;
             components.insert(component_id, unknown_storage);
         }
 
-        let locations = self.locations.get(entity);
-        EntryRef::new(entity, archetype, components, locations)
+        let locations = self
+            .locations
+            .get(entity)
+            .ok_or(EcsError::NoSuchEntity(*entity))?;
+        Ok(EntryRef::new(entity, archetype, components, locations))
+    }
+
+    /// Registers a component type so it can be found by its name
+    pub fn register(&mut self, registration: TypeRegistration) {
+        self.registry.register(registration);
+    }
+
+    /// Returns the registration of a component by its registered name
+    pub fn registration_by_name(&self, name: &str) -> Option<&TypeRegistration> {
+        self.registry.get_by_name(name)
+    }
+
+    /// Registers a component that has no rust type and creates its storage
+    /// Values of it are raw bytes described by `layout` that are dropped with `drop`
+    /// The values have to be safe to send and share between threads like every other component,
+    /// `drop` runs on whatever thread the world is dropped or cleaned up on
+    pub fn register_dynamic(
+        &mut self,
+        name: &str,
+        layout: Layout,
+        drop: Option<DropFn>,
+    ) -> ComponentId {
+        let component_id = self.registry.register_dynamic(name, layout, drop);
+        self.components
+            .create_blob_storage(component_id, layout, drop);
+
+        component_id
+    }
+
+    /// Returns the id of a component by its registered name
+    pub fn component_id(&self, name: &str) -> Option<ComponentId> {
+        self.registry
+            .get_by_name(name)
+            .map(|registration| registration.id())
+    }
+
+    /// Looks up a component of an entity by the name its type was registered with
+    pub fn component_by_name(&self, entity: &Entity, name: &str) -> Option<ComponentRef<'_>> {
+        let registration = self.registry.get_by_name(name)?;
+        let archetype = self.archetypes.find_from_entity(entity)?;
+        let index = archetype.layout().index_of(registration.id())?;
+
+        let location = &self.locations.get(entity)?[index];
+        let storage = self.components.get_storage_raw(registration.id())?;
+        let ptr = storage.get_ptr(location.component())?;
+
+        // Safety: The storage is the one of the registered type and outlives the reference
+        Some(unsafe { ComponentRef::new(ptr, registration) })
+    }
+
+    /// Returns every entity, grouped by archetype
+    /// Reserved entities are only included after a flush
+    pub fn entities(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.archetypes.iter().flat_map(Archetype::entities)
+    }
+
+    /// Returns how many entities exist
+    pub fn len(&self) -> usize {
+        self.locations.len()
+    }
+
+    /// Returns true if no entity exists
+    pub fn is_empty(&self) -> bool {
+        self.locations.is_empty()
+    }
+
+    /// Checks if an entity exists and was not despawned
+    pub fn contains(&self, entity: &Entity) -> bool {
+        self.locations.contains(entity)
+    }
+
+    /// Returns every entity that has all components of the query
+    pub fn query_entities(&self, query: &Query) -> Vec<Entity> {
+        self.archetypes.entities_with(query.components())
     }
 
     // Needs rewrite hihahuuuu
+    /// Returns every component `C` that belongs to an entity, the storage is compacted first
     pub fn query<C: Component>(&mut self) -> &[C] {
+        self.compact_component(ComponentId::of::<C>());
+
         let storage = self.components.get_storage::<C>();
         storage.as_slice()
     }
@@ -99,7 +411,15 @@ impl World {
 
 #[cfg(test)]
 mod tests {
-    use crate::storage::{Component, VecStorage};
+    use std::{alloc::Layout, mem, ptr::NonNull};
+
+    use crate::{
+        archetype::Entity,
+        error::EcsError,
+        query::Query,
+        registry::TypeRegistration,
+        storage::{Component, VecStorage},
+    };
 
     use super::World;
 
@@ -140,4 +460,202 @@ mod tests {
 
         assert_eq!(world.archetypes.len(), 2);
     }
+
+    #[test]
+    fn component_by_name() {
+        let mut world = World::new();
+        world.register(TypeRegistration::of::<Health>("health"));
+
+        let entity = world.spawn(Health(50.00));
+        world.entry_mut(&entity).add_component(Stamina(10.00));
+
+        let health = world.component_by_name(&entity, "health").unwrap();
+
+        assert_eq!(health.downcast_ref::<Health>(), Some(&Health(50.00)));
+        assert_eq!(format!("{:?}", health), "Health(50.0)");
+        assert!(health.downcast_ref::<Stamina>().is_none());
+        assert!(world.component_by_name(&entity, "stamina").is_none());
+    }
+
+    #[test]
+    fn dynamic_component_query() {
+        let mut world = World::new();
+        let mana = world.register_dynamic("mana", Layout::new::<u32>(), None);
+
+        let caster = world.spawn(Health(80.00));
+        world.spawn(Health(120.00));
+
+        let value = 42u32.to_ne_bytes();
+        unsafe {
+            world
+                .entry_mut(&caster)
+                .add_component_by_id(mana, NonNull::from(&value).cast())
+                .unwrap();
+        }
+
+        let query = Query::builder().with::<Health>().with_id(mana).build();
+        let entities = world.query_entities(&query);
+
+        assert_eq!(entities, vec![caster]);
+        assert_eq!(world.component_id("mana"), Some(mana));
+        assert_eq!(
+            world.entry(&caster).get_component_bytes(mana),
+            Some(&value[..])
+        );
+        assert_eq!(
+            *world.entry(&caster).get_component::<Health>().unwrap(),
+            Health(80.00)
+        );
+    }
+
+    #[test]
+    fn try_entry_errors() {
+        let mut world = World::new();
+
+        let entity = world.spawn(Health(100.00));
+        let despawned = world.spawn(Health(10.00));
+        world.despawn(&despawned);
+
+        let entry = world.try_entry(&entity).unwrap();
+        assert!(entry.get_component::<Stamina>().is_none());
+        assert_eq!(
+            entry.try_get_component::<Stamina>().err(),
+            Some(EcsError::MissingComponent {
+                entity,
+                component: std::any::type_name::<Stamina>()
+            })
+        );
+
+        assert_eq!(
+            world.try_entry(&despawned).err(),
+            Some(EcsError::StaleHandle(despawned))
+        );
+        assert_eq!(
+            world.try_entry(&Entity(99)).err(),
+            Some(EcsError::NoSuchEntity(Entity(99)))
+        );
+    }
+
+    #[test]
+    fn modify_component_bumps_change_tick() {
+        let mut world = World::new();
+
+        let entity = world.spawn(Health(100.00));
+        world.entry_mut(&entity).add_component(Stamina(50.00));
+
+        let last_run = world.change_tick();
+        world.increment_change_tick();
+
+        let mut entry = world.entry_mut(&entity);
+        entry.modify::<Health, _>(|health| health.0 -= 25.00);
+        entry.get_component_mut::<Health>().unwrap().0 -= 25.00;
+
+        let entry = world.entry(&entity);
+        assert_eq!(*entry.get_component::<Health>().unwrap(), Health(50.00));
+        assert!(entry.is_changed::<Health>(last_run));
+        assert!(!entry.is_changed::<Stamina>(last_run));
+    }
+
+    #[test]
+    fn add_existing_component_replaces_in_place() {
+        let mut world = World::new();
+
+        let entity = world.spawn(Health(100.00));
+        world.entry_mut(&entity).add_component(Stamina(50.00));
+        let archetypes = world.archetypes.len();
+
+        let old = world.entry_mut(&entity).add_component(Health(75.00));
+
+        assert_eq!(old, Some(Health(100.00)));
+        assert_eq!(world.archetypes.len(), archetypes);
+        assert_eq!(world.locations.get(&entity).unwrap().len(), 2);
+        assert_eq!(
+            *world.entry(&entity).get_component::<Health>().unwrap(),
+            Health(75.00)
+        );
+    }
+
+    #[test]
+    fn get_many_mut_disjoint_entities() {
+        let mut world = World::new();
+
+        let a = world.spawn(Health(100.00));
+        let b = world.spawn(Health(50.00));
+        let c = world.spawn(Stamina(10.00));
+
+        let [first, second] = world.get_many_mut::<Health, 2>([a, b]).unwrap();
+        mem::swap(first, second);
+        assert_eq!(*world.get_component::<Health>(&a).unwrap(), Health(50.00));
+
+        let mut entry = world.entry_mut(&b);
+        let [first, second] = entry.get_pair_mut::<Health>(&a).unwrap();
+        first.0 += second.0;
+        assert_eq!(*world.get_component::<Health>(&b).unwrap(), Health(150.00));
+
+        assert_eq!(
+            world.get_many_mut::<Health, 3>([a, b, a]).err(),
+            Some(EcsError::DuplicateEntity(a))
+        );
+        assert_eq!(
+            world.get_many_mut::<Health, 2>([a, c]).err(),
+            Some(EcsError::MissingComponent {
+                entity: c,
+                component: std::any::type_name::<Health>()
+            })
+        );
+    }
+
+    #[test]
+    fn enumerate_entities() {
+        let mut world = World::new();
+        let knight = world.spawn(Health(10.0));
+        let mage = world.spawn(Health(5.0));
+        world.entry_mut(&mage).add_component(Stamina(3.0));
+        let ghost = world.spawn(Stamina(1.0));
+        world.despawn(&ghost);
+        let reserved = world.reserve_entity();
+
+        assert_eq!(world.len(), 2);
+        assert!(world.contains(&knight));
+        assert!(!world.contains(&ghost));
+        assert!(!world.contains(&reserved));
+
+        world.flush();
+        let mut entities: Vec<Entity> = world.entities().collect();
+        entities.sort_by_key(Entity::id);
+        assert_eq!(entities, vec![knight, mage, reserved]);
+
+        let counts: Vec<usize> = world
+            .archetypes
+            .iter()
+            .map(|archetype| archetype.entities().count())
+            .collect();
+        assert_eq!(counts.iter().sum::<usize>(), world.len());
+    }
+
+    #[test]
+    fn world_is_shared_between_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<World>();
+
+        let mut world = World::new();
+        let knight = world.spawn(Health(10.0));
+
+        // Simulate on a worker thread, then read from several threads at once
+        let mut world = std::thread::spawn(move || {
+            world.get_component_mut::<Health>(&knight).unwrap().0 -= 4.0;
+            world
+        })
+        .join()
+        .unwrap();
+
+        std::thread::scope(|scope| {
+            for _ in 0..2 {
+                scope.spawn(|| {
+                    assert_eq!(world.get_component::<Health>(&knight), Some(&Health(6.0)));
+                });
+            }
+        });
+        world.despawn(&knight);
+    }
 }
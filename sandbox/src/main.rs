@@ -4,7 +4,7 @@ use ecs::{
 };
 
 #[derive(Debug)]
-struct Health(f32);
+struct Health(#[allow(dead_code)] f32);
 
 impl Component for Health {
     type Storage = VecStorage<Self>;